@@ -1,4 +1,11 @@
-use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
+
+use serde::{
+    de::{Error, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use crate::typing::{Signature, Type};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct Path {
@@ -28,11 +35,12 @@ impl<'s> From<&'s str> for Name {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Import {
     pub module_name: Name,
     pub items: Vec<Name>,
 }
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Export {
     pub items: Vec<Name>,
 }
@@ -40,17 +48,131 @@ pub struct Export {
 pub use expression::*;
 mod expression;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub use printer::{print_expr, print_module, PrintError};
+mod printer;
+
+pub use meta::{Meta, NodeId, Position, Span};
+mod meta;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FnDef {
+    #[serde(default)]
+    pub meta: Meta,
     pub name: Name,
-    pub parameters: Vec<Name>,
+    pub parameters: Vec<Parameter>,
+    #[serde(default)]
+    pub result: Option<Type>,
     pub expressions: Block,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FnDef {
+    /// signature described by the annotations, `any` standing for missing ones.
+    pub fn signature(&self) -> Signature {
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|p| p.ty.clone().unwrap_or(Type::Any))
+            .collect();
+        let result = self.result.clone().unwrap_or(Type::Any);
+        Signature::new(parameters, result)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Parameter {
+    pub name: Name,
+    pub ty: Option<Type>,
+}
+
+/// parameters were bare names before they had types, those are read back as
+/// untyped parameters.
+impl<'de> Deserialize<'de> for Parameter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ParameterVisitor)
+    }
+}
+
+struct ParameterVisitor;
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum ParameterField {
+    Name,
+    Ty,
+    #[serde(other)]
+    Other,
+}
+
+impl<'de> Visitor<'de> for ParameterVisitor {
+    type Value = Parameter;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("a parameter or a name")
+    }
+
+    fn visit_str<E>(self, name: &str) -> Result<Parameter, E> {
+        Ok(name.into())
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, name: D) -> Result<Parameter, D::Error> {
+        Ok(Name::deserialize(name)?.into())
+    }
+
+    /// a name as a one element tuple, or the fields in order.
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Parameter, A::Error> {
+        let name: String = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let ty = seq.next_element()?.flatten();
+        let name = name.into();
+        Ok(Parameter { name, ty })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Parameter, A::Error> {
+        let (mut name, mut ty) = (None, None);
+        while let Some(field) = map.next_key()? {
+            match field {
+                ParameterField::Name => name = Some(map.next_value()?),
+                ParameterField::Ty => ty = map.next_value()?,
+                ParameterField::Other => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        let name = name.ok_or_else(|| A::Error::missing_field("name"))?;
+        Ok(Parameter { name, ty })
+    }
+}
+
+impl From<Name> for Parameter {
+    fn from(name: Name) -> Self {
+        Self { name, ty: None }
+    }
+}
+
+impl<'s> From<&'s str> for Parameter {
+    fn from(input: &'s str) -> Self {
+        Name::from(input).into()
+    }
+}
+
+/// value defined at the top of a module, its initializer being evaluated
+/// once when the module is registered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValueDef {
+    pub name: Name,
+    pub value: BExpr,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TopLevel {
+    Import(Import),
     Export(Export),
     FnDef(FnDef),
+    /// value the functions of the module can only read.
+    Const(ValueDef),
+    /// value the functions of the module can read and assign.
+    Global(ValueDef),
 }
 
 impl TopLevel {
@@ -68,7 +190,7 @@ impl TopLevel {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Module {
     pub items: Vec<TopLevel>,
 }
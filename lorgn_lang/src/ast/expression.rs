@@ -1,24 +1,78 @@
-use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
 
-use super::{Name, Path};
+use serde::{
+    de::{
+        value::EnumAccessDeserializer, DeserializeSeed, EnumAccess, IntoDeserializer,
+        VariantAccess, Visitor,
+    },
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::typing::Type;
+
+use super::{Meta, Name, Parameter, Path};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Block {
     pub expressions: Vec<BExpr>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// update of the variable visible under a name, declaring it in the
+/// current block when there is none.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Assignment {
     pub variable_name: Name,
     pub value: BExpr,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// new variable in the current block, shadowing any outer one of the same
+/// name until the end of the block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Declaration {
+    pub variable_name: Name,
+    pub value: BExpr,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Invoke {
     pub variable_name: Name,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// constant or global of a module.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    pub item_path: Path,
+}
+
+/// element of a list at an integer index, or field of an object at a
+/// string key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Index {
+    pub target: BExpr,
+    pub index: BExpr,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexAssignment {
+    pub target: BExpr,
+    pub index: BExpr,
+    pub value: BExpr,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Field {
+    pub target: BExpr,
+    pub field: Name,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldAssignment {
+    pub target: BExpr,
+    pub field: Name,
+    pub value: BExpr,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Litteral {
     String(String),
     Integer(i32),
@@ -65,7 +119,7 @@ where
     fn from(input: Vec<T>) -> Self {
         let list = input
             .into_iter()
-            .map(|e| Box::new(Expr::Litteral(e.into())))
+            .map(|e| Expr::Litteral(e.into()).boxed())
             .collect();
         Self::List(list)
     }
@@ -79,47 +133,151 @@ where
     fn from(input: Vec<(S, T)>) -> Self {
         let list = input
             .into_iter()
-            .map(|(n, e)| (n.to_string().into(), Box::new(Expr::Litteral(e.into()))))
+            .map(|(n, e)| (n.to_string().into(), Expr::Litteral(e.into()).boxed()))
             .collect();
         Self::Map(list)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FnCall {
     pub fn_path: Path,
     pub arguments: Vec<BExpr>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// anonymous function capturing the variables visible where it is evaluated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lambda {
+    pub parameters: Vec<Parameter>,
+    #[serde(default)]
+    pub result: Option<Type>,
+    pub expressions: Block,
+}
+
+/// call of a function value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Call {
+    pub function: BExpr,
+    pub arguments: Vec<BExpr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOperator {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::And => "and",
+            Self::Or => "or",
+        }
+    }
+
+    /// binding strength, operators of higher precedence grouping first.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And => 2,
+            Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge => 3,
+            Self::Add | Self::Sub => 4,
+            Self::Mul | Self::Div | Self::Rem => 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UnaryOperator {
+    Neg,
+    Not,
+}
+
+impl UnaryOperator {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Neg => "-",
+            Self::Not => "not",
+        }
+    }
+}
+
+/// operation on two operands, `and` and `or` only evaluating the right one
+/// when needed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BinaryOp {
+    pub operator: BinaryOperator,
+    pub left: BExpr,
+    pub right: BExpr,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnaryOp {
+    pub operator: UnaryOperator,
+    pub operand: BExpr,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Condition {
     pub condition: BExpr,
     pub true_case: BExpr,
     pub false_case: BExpr,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Loop {
     pub body: BExpr,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Return {
     pub expression: BExpr,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Break {
     pub expression: BExpr,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Expr {
     Block(Block),
     Assignment(Assignment),
+    Declaration(Declaration),
     Invoke(Invoke),
+    Item(Item),
+    Index(Index),
+    IndexAssignment(IndexAssignment),
+    Field(Field),
+    FieldAssignment(FieldAssignment),
     Litteral(Litteral),
     FnCall(FnCall),
+    Lambda(Lambda),
+    Call(Call),
+    BinaryOp(BinaryOp),
+    UnaryOp(UnaryOp),
     Condition(Condition),
     Loop(Loop),
     Return(Return),
@@ -128,8 +286,251 @@ pub enum Expr {
 
 impl Expr {
     pub fn boxed(self) -> BExpr {
-        Box::new(self)
+        Box::new(self.into())
+    }
+
+    pub fn children(&self) -> Vec<&Node> {
+        match self {
+            Self::Block(block) => block.expressions.iter().map(|e| &**e).collect(),
+            Self::Assignment(assignment) => vec![&assignment.value],
+            Self::Declaration(declaration) => vec![&declaration.value],
+            Self::Invoke(_) | Self::Item(_) => vec![],
+            Self::Index(index) => vec![&index.target, &index.index],
+            Self::IndexAssignment(assignment) => {
+                vec![&assignment.target, &assignment.index, &assignment.value]
+            }
+            Self::Field(field) => vec![&field.target],
+            Self::FieldAssignment(assignment) => vec![&assignment.target, &assignment.value],
+            Self::Litteral(Litteral::List(list)) => list.iter().map(|e| &**e).collect(),
+            Self::Litteral(Litteral::Map(map)) => map.iter().map(|(_, e)| &**e).collect(),
+            Self::Litteral(_) => vec![],
+            Self::FnCall(fn_call) => fn_call.arguments.iter().map(|e| &**e).collect(),
+            Self::Lambda(lambda) => lambda
+                .expressions
+                .expressions
+                .iter()
+                .map(|e| &**e)
+                .collect(),
+            Self::Call(call) => {
+                let arguments = call.arguments.iter().map(|e| &**e);
+                std::iter::once(&*call.function).chain(arguments).collect()
+            }
+            Self::BinaryOp(binary_op) => vec![&binary_op.left, &binary_op.right],
+            Self::UnaryOp(unary_op) => vec![&unary_op.operand],
+            Self::Condition(condition) => vec![
+                &condition.condition,
+                &condition.true_case,
+                &condition.false_case,
+            ],
+            Self::Loop(loop_) => vec![&loop_.body],
+            Self::Return(return_) => vec![&return_.expression],
+            Self::Break(break_) => vec![&break_.expression],
+        }
+    }
+
+    pub fn children_mut(&mut self) -> Vec<&mut Node> {
+        match self {
+            Self::Block(block) => block.expressions.iter_mut().map(|e| &mut **e).collect(),
+            Self::Assignment(assignment) => vec![&mut assignment.value],
+            Self::Declaration(declaration) => vec![&mut declaration.value],
+            Self::Invoke(_) | Self::Item(_) => vec![],
+            Self::Index(index) => vec![&mut index.target, &mut index.index],
+            Self::IndexAssignment(assignment) => vec![
+                &mut assignment.target,
+                &mut assignment.index,
+                &mut assignment.value,
+            ],
+            Self::Field(field) => vec![&mut field.target],
+            Self::FieldAssignment(assignment) => {
+                vec![&mut assignment.target, &mut assignment.value]
+            }
+            Self::Litteral(Litteral::List(list)) => list.iter_mut().map(|e| &mut **e).collect(),
+            Self::Litteral(Litteral::Map(map)) => map.iter_mut().map(|(_, e)| &mut **e).collect(),
+            Self::Litteral(_) => vec![],
+            Self::FnCall(fn_call) => fn_call.arguments.iter_mut().map(|e| &mut **e).collect(),
+            Self::Lambda(lambda) => {
+                let expressions = lambda.expressions.expressions.iter_mut();
+                expressions.map(|e| &mut **e).collect()
+            }
+            Self::Call(call) => {
+                let arguments = call.arguments.iter_mut().map(|e| &mut **e);
+                std::iter::once(&mut *call.function)
+                    .chain(arguments)
+                    .collect()
+            }
+            Self::BinaryOp(binary_op) => vec![&mut binary_op.left, &mut binary_op.right],
+            Self::UnaryOp(unary_op) => vec![&mut unary_op.operand],
+            Self::Condition(condition) => vec![
+                &mut condition.condition,
+                &mut condition.true_case,
+                &mut condition.false_case,
+            ],
+            Self::Loop(loop_) => vec![&mut loop_.body],
+            Self::Return(return_) => vec![&mut return_.expression],
+            Self::Break(break_) => vec![&mut break_.expression],
+        }
     }
 }
 
-pub type BExpr = Box<Expr>;
+/// an expression along with its identity and location.
+///
+/// nodes without either serialize as their bare expression, as children
+/// were before nodes existed, and others as a `Node` variant holding both.
+/// both forms are read back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub meta: Meta,
+    pub expr: Expr,
+}
+
+/// variant standing for a node among those of `Expr`.
+const NODE_VARIANT: &str = "Node";
+/// index of the node variant, out of reach of those of `Expr`.
+const NODE_INDEX: u32 = u32::MAX;
+
+#[derive(Serialize)]
+struct NodeRef<'n> {
+    meta: &'n Meta,
+    expr: &'n Expr,
+}
+
+#[derive(Deserialize)]
+struct NodeFields {
+    #[serde(default)]
+    meta: Meta,
+    expr: Expr,
+}
+
+impl Serialize for Node {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.meta == Meta::default() {
+            return self.expr.serialize(serializer);
+        }
+        let node = NodeRef {
+            meta: &self.meta,
+            expr: &self.expr,
+        };
+        serializer.serialize_newtype_variant("Expr", NODE_INDEX, NODE_VARIANT, &node)
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_enum("Expr", &[NODE_VARIANT], NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("an expression or a node")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Node, A::Error> {
+        let (tag, variant) = data.variant::<Tag>()?;
+        let is_node = match &tag {
+            Tag::Name(name) => name == NODE_VARIANT,
+            Tag::Index(index) => *index == NODE_INDEX as u64,
+        };
+        if is_node {
+            let NodeFields { meta, expr } = variant.newtype_variant()?;
+            return Ok(Node { meta, expr });
+        }
+        // the variant was read already, it is handed back to `Expr`.
+        let expr = Expr::deserialize(EnumAccessDeserializer::new(Tagged { tag, variant }))?;
+        Ok(expr.into())
+    }
+}
+
+/// variant of an enum, named or indexed depending on the format.
+enum Tag {
+    Name(String),
+    Index(u64),
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_identifier(TagVisitor)
+    }
+}
+
+struct TagVisitor;
+
+impl Visitor<'_> for TagVisitor {
+    type Value = Tag;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("a variant")
+    }
+
+    fn visit_u64<E>(self, index: u64) -> Result<Tag, E> {
+        Ok(Tag::Index(index))
+    }
+
+    fn visit_str<E>(self, name: &str) -> Result<Tag, E> {
+        Ok(Tag::Name(name.to_string()))
+    }
+}
+
+/// enum whose variant was already read.
+struct Tagged<V> {
+    tag: Tag,
+    variant: V,
+}
+
+impl<'de, V: VariantAccess<'de>> EnumAccess<'de> for Tagged<V> {
+    type Error = V::Error;
+    type Variant = V;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, V), V::Error> {
+        let value = match self.tag {
+            Tag::Name(name) => seed.deserialize(name.into_deserializer())?,
+            Tag::Index(index) => seed.deserialize(index.into_deserializer())?,
+        };
+        Ok((value, self.variant))
+    }
+}
+
+impl From<Expr> for Node {
+    fn from(expr: Expr) -> Self {
+        let meta = Meta::default();
+        Self { meta, expr }
+    }
+}
+
+pub type BExpr = Box<Node>;
+
+#[test]
+fn test_node_serialization() {
+    use super::Module;
+    use crate::parser::parse_module;
+
+    // serialized before nodes had ids and spans.
+    let old = r#"(items:[Export((items:[("main")])),FnDef((name:("main"),parameters:[],expressions:(expressions:[Assignment((variable_name:("x"),value:Litteral(List([Litteral(Integer(1)),Litteral(Float(2.5))])))),Condition((condition:FnCall((fn_path:(module:("std::math"),item:("eq")),arguments:[Invoke((variable_name:("x"))),Litteral(Integer(1))])),true_case:FnCall((fn_path:(module:("main"),item:("main")),arguments:[])),false_case:Invoke((variable_name:("x")))))])))])"#;
+    let source = "export { main }; fn main() { x = [1, 2.5]; if std::math::eq(x, 1) then main::main() else x }";
+    let mut expected = parse_module(source).unwrap();
+    expected.for_each_meta_mut(&mut |meta| *meta = Meta::default());
+    let module: Module = ron::from_str(old).unwrap();
+    assert_eq!(module, expected);
+    // nodes without meta keep the form of their expression.
+    let expr = Expr::Invoke(Invoke {
+        variable_name: "x".into(),
+    });
+    let node = ron::to_string(&Node::from(expr.clone())).unwrap();
+    assert_eq!(node, ron::to_string(&expr).unwrap());
+
+    // parameters were bare names before they had types.
+    let old = old.replace("parameters:[]", "parameters:[(\"n\")]");
+    let source = source.replace("fn main()", "fn main(n)");
+    let mut expected = parse_module(&source).unwrap();
+    expected.for_each_meta_mut(&mut |meta| *meta = Meta::default());
+    assert_eq!(ron::from_str::<Module>(&old).unwrap(), expected);
+
+    let module = parse_module("fn typed(a, b: integer) -> float { a }").unwrap();
+    let serialized = ron::to_string(&module).unwrap();
+    assert!(serialized.contains("Node((meta:(id:Some("));
+    assert_eq!(ron::from_str::<Module>(&serialized).unwrap(), module);
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Module, Node, TopLevel};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NodeId(pub u64);
+
+/// identity and location of a node, both missing on nodes built by hand
+/// until ids are assigned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Meta {
+    pub id: Option<NodeId>,
+    pub span: Option<Span>,
+}
+
+impl Meta {
+    pub fn spanned(span: Span) -> Self {
+        let span = Some(span);
+        Self { id: None, span }
+    }
+}
+
+impl Node {
+    pub fn for_each_meta_mut(&mut self, visitor: &mut impl FnMut(&mut Meta)) {
+        visitor(&mut self.meta);
+        for child in self.expr.children_mut() {
+            child.for_each_meta_mut(visitor);
+        }
+    }
+
+    /// gives a fresh id to every node lacking one, in pre-order.
+    pub fn assign_ids(&mut self) {
+        let mut next = 0;
+        self.for_each_meta_mut(&mut |meta| next = next.max(meta.id.map_or(0, |NodeId(id)| id)));
+        self.for_each_meta_mut(&mut |meta| fill_id(meta, &mut next));
+    }
+
+    pub fn strip_spans(&mut self) {
+        self.for_each_meta_mut(&mut |meta| meta.span = None);
+    }
+}
+
+impl Module {
+    pub fn for_each_meta_mut(&mut self, visitor: &mut impl FnMut(&mut Meta)) {
+        for item in &mut self.items {
+            match item {
+                TopLevel::FnDef(fndef) => {
+                    visitor(&mut fndef.meta);
+                    for expr in &mut fndef.expressions.expressions {
+                        expr.for_each_meta_mut(visitor);
+                    }
+                }
+                TopLevel::Const(definition) | TopLevel::Global(definition) => {
+                    definition.value.for_each_meta_mut(visitor)
+                }
+                TopLevel::Import(_) | TopLevel::Export(_) => (),
+            }
+        }
+    }
+
+    /// gives a fresh id to every node lacking one, in pre-order, keeping
+    /// existing ids untouched.
+    pub fn assign_ids(&mut self) {
+        let mut next = 0;
+        self.for_each_meta_mut(&mut |meta| next = next.max(meta.id.map_or(0, |NodeId(id)| id)));
+        self.for_each_meta_mut(&mut |meta| fill_id(meta, &mut next));
+    }
+
+    pub fn strip_spans(&mut self) {
+        self.for_each_meta_mut(&mut |meta| meta.span = None);
+    }
+}
+
+fn fill_id(meta: &mut Meta, last: &mut u64) {
+    if meta.id.is_none() {
+        *last += 1;
+        meta.id = Some(NodeId(*last));
+    }
+}
@@ -0,0 +1,667 @@
+use std::fmt::Display;
+
+use crate::parser::is_keyword;
+
+use crate::typing::Type;
+
+use super::{
+    Block, Export, Expr, FnDef, Import, Litteral, Module, Name, Node, Parameter, Path, TopLevel,
+    UnaryOperator, ValueDef,
+};
+
+const INDENT: &str = "    ";
+
+/// binding strength of calls, indexings and field accesses.
+const POSTFIX: u8 = 7;
+/// binding strength of unary operations.
+const UNARY: u8 = 6;
+
+/// part of an ast which has no textual form.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrintError {
+    /// name which would not read back as an identifier.
+    InvalidName(Name),
+    /// float without a litteral, as `NaN`.
+    InvalidFloat(f32),
+}
+
+impl Display for PrintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidName(Name(name)) => write!(f, "'{name}' is not a valid name"),
+            Self::InvalidFloat(flt) => write!(f, "float {flt} has no litteral"),
+        }
+    }
+}
+
+impl std::error::Error for PrintError {}
+
+/// renders a module as canonical text, parsing it back yields the same
+/// module up to node spans and ids, the parser numbering nodes afresh in
+/// pre-order.
+pub fn print_module(module: &Module) -> Result<String, PrintError> {
+    let mut printer = Printer::default();
+    printer.module(module);
+    printer.finish()
+}
+
+pub fn print_expr(expr: &Expr) -> Result<String, PrintError> {
+    let mut printer = Printer::default();
+    printer.expr(expr);
+    printer.finish()
+}
+
+/// renders the module as text even when it does not read back, see
+/// `print_module`.
+impl Display for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut printer = Printer::default();
+        printer.module(self);
+        f.write_str(&printer.output)
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut printer = Printer::default();
+        printer.expr(self);
+        f.write_str(&printer.output)
+    }
+}
+
+#[derive(Default)]
+struct Printer {
+    output: String,
+    indent: usize,
+    /// first part met which does not read back.
+    error: Option<PrintError>,
+}
+
+impl Printer {
+    fn finish(self) -> Result<String, PrintError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.output),
+        }
+    }
+
+    fn fail(&mut self, error: PrintError) {
+        self.error.get_or_insert(error);
+    }
+
+    fn push(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        self.output.push('\n');
+        for _ in 0..self.indent {
+            self.output.push_str(INDENT);
+        }
+    }
+
+    fn separated<T>(&mut self, items: &[T], mut item: impl FnMut(&mut Self, &T)) {
+        for (index, element) in items.iter().enumerate() {
+            if index > 0 {
+                self.push(", ");
+            }
+            item(self, element);
+        }
+    }
+
+    fn name(&mut self, name: &Name) {
+        if !is_identifier(&name.0) {
+            self.fail(PrintError::InvalidName(name.clone()));
+        }
+        self.push(&name.0);
+    }
+
+    /// name of a module, made of identifiers separated by `::`.
+    fn module_name(&mut self, name: &Name) {
+        if !name.0.split("::").all(is_identifier) {
+            self.fail(PrintError::InvalidName(name.clone()));
+        }
+        self.push(&name.0);
+    }
+
+    fn module(&mut self, module: &Module) {
+        let mut previous: Option<&TopLevel> = None;
+        for item in &module.items {
+            let is_fndef = |item: &TopLevel| matches!(item, TopLevel::FnDef(_));
+            if let Some(previous) = previous {
+                if is_fndef(previous) || is_fndef(item) {
+                    self.push("\n");
+                }
+            }
+            self.top_level(item);
+            self.push("\n");
+            previous = Some(item);
+        }
+    }
+
+    fn top_level(&mut self, item: &TopLevel) {
+        match item {
+            TopLevel::Import(import) => self.import(import),
+            TopLevel::Export(export) => self.export(export),
+            TopLevel::FnDef(fndef) => self.fndef(fndef),
+            TopLevel::Const(definition) => self.value_def("const ", definition),
+            TopLevel::Global(definition) => self.value_def("global ", definition),
+        }
+    }
+
+    fn value_def(&mut self, keyword: &str, ValueDef { name, value }: &ValueDef) {
+        self.push(keyword);
+        self.name(name);
+        self.push(" = ");
+        self.node(value);
+        self.push(";");
+    }
+
+    fn import(&mut self, Import { module_name, items }: &Import) {
+        self.push("import ");
+        self.module_name(module_name);
+        self.push("::{");
+        self.separated(items, Self::name);
+        self.push("};");
+    }
+
+    fn export(&mut self, Export { items }: &Export) {
+        if items.is_empty() {
+            self.push("export {};");
+            return;
+        }
+        self.push("export { ");
+        self.separated(items, Self::name);
+        self.push(" };");
+    }
+
+    fn fndef(&mut self, fndef: &FnDef) {
+        self.push("fn ");
+        self.name(&fndef.name);
+        self.function(&fndef.parameters, &fndef.result, &fndef.expressions);
+    }
+
+    fn function(&mut self, parameters: &[Parameter], result: &Option<Type>, body: &Block) {
+        self.push("(");
+        self.separated(parameters, |p, parameter| {
+            p.name(&parameter.name);
+            if let Some(ty) = &parameter.ty {
+                p.push(": ");
+                p.push(ty.name());
+            }
+        });
+        self.push(") ");
+        if let Some(ty) = result {
+            self.push("-> ");
+            self.push(ty.name());
+            self.push(" ");
+        }
+        self.block(body);
+    }
+
+    fn block(&mut self, Block { expressions }: &Block) {
+        if expressions.is_empty() {
+            self.push("{}");
+            return;
+        }
+        self.push("{");
+        self.indent += 1;
+        for expr in expressions {
+            self.newline();
+            self.node(expr);
+            self.push(";");
+        }
+        self.indent -= 1;
+        self.newline();
+        self.push("}");
+    }
+
+    fn path(&mut self, Path { module, item }: &Path) {
+        self.module_name(module);
+        self.push("::");
+        self.name(item);
+    }
+
+    fn node(&mut self, node: &Node) {
+        self.expr(&node.expr);
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Block(block) => self.block(block),
+            Expr::Assignment(assignment) => {
+                self.name(&assignment.variable_name);
+                self.push(" = ");
+                self.node(&assignment.value);
+            }
+            Expr::Declaration(declaration) => {
+                self.push("let ");
+                self.name(&declaration.variable_name);
+                self.push(" = ");
+                self.node(&declaration.value);
+            }
+            Expr::Invoke(invoke) => self.name(&invoke.variable_name),
+            Expr::Item(item) => self.path(&item.item_path),
+            Expr::Index(index) => {
+                self.postfix_target(&index.target);
+                self.push("[");
+                self.node(&index.index);
+                self.push("]");
+            }
+            Expr::IndexAssignment(assignment) => {
+                self.postfix_target(&assignment.target);
+                self.push("[");
+                self.node(&assignment.index);
+                self.push("] = ");
+                self.node(&assignment.value);
+            }
+            Expr::Field(field) => {
+                self.postfix_target(&field.target);
+                self.push(".");
+                self.map_key(&field.field);
+            }
+            Expr::FieldAssignment(assignment) => {
+                self.postfix_target(&assignment.target);
+                self.push(".");
+                self.map_key(&assignment.field);
+                self.push(" = ");
+                self.node(&assignment.value);
+            }
+            Expr::Litteral(litteral) => self.litteral(litteral),
+            Expr::FnCall(fn_call) => {
+                self.path(&fn_call.fn_path);
+                self.push("(");
+                self.separated(&fn_call.arguments, |p, arg| p.node(arg));
+                self.push(")");
+            }
+            Expr::Lambda(lambda) => {
+                self.push("fn");
+                self.function(&lambda.parameters, &lambda.result, &lambda.expressions);
+            }
+            // a path followed by arguments would read as a call by path.
+            Expr::Call(call) if matches!(call.function.expr, Expr::Item(_)) => {
+                self.push("(");
+                self.node(&call.function);
+                self.push(")(");
+                self.separated(&call.arguments, |p, arg| p.node(arg));
+                self.push(")");
+            }
+            Expr::Call(call) => {
+                self.postfix_target(&call.function);
+                self.push("(");
+                self.separated(&call.arguments, |p, arg| p.node(arg));
+                self.push(")");
+            }
+            Expr::BinaryOp(binary_op) => {
+                let precedence = binary_op.operator.precedence();
+                self.grouped(&binary_op.left, precedence);
+                self.push(" ");
+                self.push(binary_op.operator.symbol());
+                self.push(" ");
+                self.grouped(&binary_op.right, precedence + 1);
+            }
+            Expr::UnaryOp(unary_op) => {
+                self.push(unary_op.operator.symbol());
+                let operand = &unary_op.operand;
+                match unary_op.operator {
+                    UnaryOperator::Not => self.push(" "),
+                    // a minus sign followed by a number would read as a litteral.
+                    UnaryOperator::Neg if is_number(&operand.expr) => {
+                        self.push("(");
+                        self.node(operand);
+                        self.push(")");
+                        return;
+                    }
+                    UnaryOperator::Neg => (),
+                }
+                self.grouped(operand, UNARY);
+            }
+            Expr::Condition(condition) => {
+                self.push("if ");
+                self.node(&condition.condition);
+                self.push(" then ");
+                self.node(&condition.true_case);
+                self.push(" else ");
+                self.node(&condition.false_case);
+            }
+            Expr::Loop(loop_) => {
+                self.push("loop ");
+                self.node(&loop_.body);
+            }
+            Expr::Return(return_) => {
+                self.push("return ");
+                self.node(&return_.expression);
+            }
+            Expr::Break(break_) => {
+                self.push("break ");
+                self.node(&break_.expression);
+            }
+        }
+    }
+
+    /// prints the target of a call, index or field access.
+    fn postfix_target(&mut self, target: &Node) {
+        self.grouped(target, POSTFIX);
+    }
+
+    /// prints a node, between parentheses when it binds looser than
+    /// `strength`.
+    fn grouped(&mut self, node: &Node, strength: u8) {
+        if binding(&node.expr) < strength {
+            self.push("(");
+            self.node(node);
+            self.push(")");
+        } else {
+            self.node(node);
+        }
+    }
+
+    fn litteral(&mut self, litteral: &Litteral) {
+        match litteral {
+            Litteral::String(str) => self.string(str),
+            Litteral::Integer(int) => self.push(&int.to_string()),
+            Litteral::Float(flt) => self.float(*flt),
+            Litteral::Bool(bool) => self.push(&bool.to_string()),
+            Litteral::List(list) => {
+                self.push("[");
+                self.separated(list, |p, item| p.node(item));
+                self.push("]");
+            }
+            Litteral::Map(map) if map.is_empty() => self.push("#{}"),
+            Litteral::Map(map) => {
+                self.push("#{ ");
+                self.separated(map, |p, (key, value)| {
+                    p.map_key(key);
+                    p.push(": ");
+                    p.node(value);
+                });
+                self.push(" }");
+            }
+        }
+    }
+
+    fn float(&mut self, flt: f32) {
+        if flt.is_nan() {
+            self.fail(PrintError::InvalidFloat(flt));
+            self.push("NaN");
+        } else if flt.is_infinite() {
+            // overflowing litterals read back as infinities.
+            self.push(if flt < 0.0 { "-1e39" } else { "1e39" });
+        } else {
+            self.push(&format!("{flt:?}"));
+        }
+    }
+
+    fn map_key(&mut self, Name(key): &Name) {
+        if is_identifier(key) {
+            self.push(key);
+        } else {
+            self.string(key);
+        }
+    }
+
+    fn string(&mut self, str: &str) {
+        self.push("\"");
+        for c in str.chars() {
+            match c {
+                '\n' => self.push("\\n"),
+                '\t' => self.push("\\t"),
+                '\r' => self.push("\\r"),
+                '\0' => self.push("\\0"),
+                '\\' => self.push("\\\\"),
+                '"' => self.push("\\\""),
+                c => self.output.push(c),
+            }
+        }
+        self.push("\"");
+    }
+}
+
+/// how tightly an expression holds together, keyword led expressions and
+/// assignments extending as far right as they can.
+fn binding(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Assignment(_)
+        | Expr::Declaration(_)
+        | Expr::IndexAssignment(_)
+        | Expr::FieldAssignment(_)
+        | Expr::Condition(_)
+        | Expr::Loop(_)
+        | Expr::Return(_)
+        | Expr::Break(_) => 0,
+        Expr::BinaryOp(binary_op) => binary_op.operator.precedence(),
+        Expr::UnaryOp(_) => UNARY,
+        _ => POSTFIX,
+    }
+}
+
+/// whether a name reads back as a single identifier.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && !is_keyword(name)
+}
+
+/// whether the printed expression starts with a number.
+fn is_number(expr: &Expr) -> bool {
+    match expr {
+        Expr::Litteral(Litteral::Integer(_) | Litteral::Float(_)) => true,
+        Expr::Call(call) => is_number(&call.function.expr),
+        Expr::Index(index) => is_number(&index.target.expr),
+        Expr::Field(field) => is_number(&field.target.expr),
+        _ => false,
+    }
+}
+
+#[test]
+fn test_print_round_trip() {
+    use super::{Meta, NodeId};
+    use crate::parser::{parse_expr, parse_module};
+
+    let source = r#"
+        import std::math::{mul, sub};
+        export { fact, misc };
+        const LIMIT = 3;   global count = #{ n: main::LIMIT - 1 };
+        fn fact(n: integer) -> integer {
+            if std::math::eq(n, 0) then 1 else {
+                m = std::math::sub(n, 1);
+                return std::math::mul(n, main::fact(m))
+            }
+        }
+        fn misc() { loop { break [1, -2.5, 1e-7, "a\"b\n", true, #{ x: 1, "y z": #{} }] } }
+        fn empty() {}
+        fn apply(f) { let twice = fn(x: integer) -> integer { f(f(x)) }; twice(1)(2) }
+        fn places(o) { o.list[0] = o."a b"; o.x = [1][0]; o["y"].z }
+        fn ops(a, b) { (a + b) * -a - -1 == a % 2 or not (a < b) and (-b)(1) }
+    "#;
+    let module = parse_module(source).unwrap();
+    let printed = print_module(&module).unwrap();
+    let expected = r#"import std::math::{mul, sub};
+export { fact, misc };
+const LIMIT = 3;
+global count = #{ n: main::LIMIT - 1 };
+
+fn fact(n: integer) -> integer {
+    if std::math::eq(n, 0) then 1 else {
+        m = std::math::sub(n, 1);
+        return std::math::mul(n, main::fact(m));
+    };
+}
+
+fn misc() {
+    loop {
+        break [1, -2.5, 1e-7, "a\"b\n", true, #{ x: 1, "y z": #{} }];
+    };
+}
+
+fn empty() {}
+
+fn apply(f) {
+    let twice = fn(x: integer) -> integer {
+        f(f(x));
+    };
+    twice(1)(2);
+}
+
+fn places(o) {
+    o.list[0] = o."a b";
+    o.x = [1][0];
+    o["y"].z;
+}
+
+fn ops(a, b) {
+    (a + b) * -a - -1 == a % 2 or not (a < b) and (-b)(1);
+}
+"#;
+    assert_eq!(printed, expected);
+    let mut module = module;
+    let mut reparsed = parse_module(&printed).unwrap();
+    module.strip_spans();
+    reparsed.strip_spans();
+    assert_eq!(reparsed, module);
+    assert_eq!(print_module(&reparsed), Ok(printed));
+
+    // an edited module reads back the same once ids are set aside.
+    let mut edited = module.clone();
+    let TopLevel::FnDef(fact) = &mut edited.items[4] else {
+        panic!("fact is the fifth item");
+    };
+    let mut inserted = parse_expr("std::print(n)").unwrap();
+    inserted.for_each_meta_mut(&mut |meta| *meta = Meta::default());
+    fact.expressions.expressions.insert(0, Box::new(inserted));
+    let mut last = 0;
+    module.for_each_meta_mut(&mut |meta| last = last.max(meta.id.unwrap().0));
+    edited.assign_ids();
+    let TopLevel::FnDef(fact) = &edited.items[4] else {
+        panic!("fact is the fifth item");
+    };
+    assert_eq!(
+        fact.expressions.expressions[0].meta.id,
+        Some(NodeId(last + 1))
+    );
+    let mut reparsed = parse_module(&print_module(&edited).unwrap()).unwrap();
+    reparsed.strip_spans();
+    assert_ne!(reparsed, edited);
+    let clear_ids = |module: &mut Module| module.for_each_meta_mut(&mut |meta| meta.id = None);
+    clear_ids(&mut reparsed);
+    clear_ids(&mut edited);
+    assert_eq!(reparsed, edited);
+
+    let expr = Expr::Litteral(Litteral::Map(vec![
+        ("if".into(), Expr::Litteral(i32::MIN.into()).boxed()),
+        ("tab\t".into(), Expr::Litteral(f32::MAX.into()).boxed()),
+    ]));
+    let mut reparsed = parse_expr(&expr.to_string()).unwrap();
+    reparsed.for_each_meta_mut(&mut |meta| *meta = Meta::default());
+    assert_eq!(reparsed.expr, expr);
+
+    let call = Expr::Call(super::Call {
+        function: Expr::Loop(super::Loop {
+            body: Expr::Invoke(super::Invoke {
+                variable_name: "f".into(),
+            })
+            .boxed(),
+        })
+        .boxed(),
+        arguments: vec![],
+    });
+    assert_eq!(call.to_string(), "(loop f)()");
+    let mut reparsed = parse_expr(&call.to_string()).unwrap();
+    reparsed.for_each_meta_mut(&mut |meta| *meta = Meta::default());
+    assert_eq!(reparsed.expr, call);
+
+    let number = |value: i32| Expr::Litteral(value.into()).boxed();
+    let negation = Expr::UnaryOp(super::UnaryOp {
+        operator: UnaryOperator::Neg,
+        operand: Expr::BinaryOp(super::BinaryOp {
+            operator: super::BinaryOperator::Sub,
+            left: number(1),
+            right: Expr::BinaryOp(super::BinaryOp {
+                operator: super::BinaryOperator::Add,
+                left: number(2),
+                right: Expr::UnaryOp(super::UnaryOp {
+                    operator: UnaryOperator::Neg,
+                    operand: number(3),
+                })
+                .boxed(),
+            })
+            .boxed(),
+        })
+        .boxed(),
+    });
+    assert_eq!(negation.to_string(), "-(1 - (2 + -(3)))");
+    let mut reparsed = parse_expr(&negation.to_string()).unwrap();
+    reparsed.for_each_meta_mut(&mut |meta| *meta = Meta::default());
+    assert_eq!(reparsed.expr, negation);
+}
+
+#[test]
+fn test_print_edge_cases() {
+    use super::{Call, Invoke, Item, Meta};
+    use crate::parser::parse_expr;
+
+    let round_trip = |expr: &Expr| {
+        let printed = print_expr(expr).unwrap();
+        let mut reparsed = parse_expr(&printed).unwrap();
+        reparsed.for_each_meta_mut(&mut |meta| *meta = Meta::default());
+        assert_eq!(&reparsed.expr, expr, "{printed}");
+        printed
+    };
+
+    let item = |module: &str, item: &str| {
+        Expr::Item(Item {
+            item_path: Path {
+                module: module.into(),
+                item: item.into(),
+            },
+        })
+    };
+    let call = Expr::Call(Call {
+        function: item("std::math", "F").boxed(),
+        arguments: vec![Expr::Litteral(1.into()).boxed()],
+    });
+    assert_eq!(round_trip(&call), "(std::math::F)(1)");
+
+    let infinities = Expr::Litteral(Litteral::List(vec![
+        Expr::Litteral(f32::INFINITY.into()).boxed(),
+        Expr::Litteral(f32::NEG_INFINITY.into()).boxed(),
+    ]));
+    assert_eq!(round_trip(&infinities), "[1e39, -1e39]");
+    let nan = Expr::Litteral(f32::NAN.into());
+    assert!(matches!(print_expr(&nan), Err(PrintError::InvalidFloat(_))));
+
+    let invoke = |name: &str| {
+        Expr::Invoke(Invoke {
+            variable_name: name.into(),
+        })
+    };
+    assert_eq!(round_trip(&invoke("_snake_2")), "_snake_2");
+    for name in ["two words", "loop", "", "1st", "a::b"] {
+        assert_eq!(
+            print_expr(&invoke(name)),
+            Err(PrintError::InvalidName(name.into()))
+        );
+    }
+    assert_eq!(round_trip(&item("std::math", "PI")), "std::math::PI");
+    assert_eq!(
+        print_expr(&item("std::if", "PI")),
+        Err(PrintError::InvalidName("std::if".into()))
+    );
+    let fndef = |name: &str| Module {
+        items: vec![TopLevel::FnDef(FnDef {
+            meta: Meta::default(),
+            name: name.into(),
+            parameters: vec![],
+            result: None,
+            expressions: Block {
+                expressions: vec![],
+            },
+        })],
+    };
+    assert_eq!(print_module(&fndef("main")), Ok("fn main() {}\n".into()));
+    assert_eq!(
+        print_module(&fndef("my fn")),
+        Err(PrintError::InvalidName("my fn".into()))
+    );
+}
@@ -0,0 +1,614 @@
+use std::fmt::Display;
+
+use crate::ast::{
+    Assignment, BExpr, BinaryOp, BinaryOperator, Block, Break, Call, Condition, Declaration,
+    Export, Expr, Field, FieldAssignment, FnCall, FnDef, Import, Index, IndexAssignment, Invoke,
+    Item, Lambda, Litteral, Loop, Meta, Module, Name, Node, Parameter, Path, Position, Return,
+    Span, TopLevel, UnaryOp, UnaryOperator, ValueDef,
+};
+use crate::typing::Type;
+
+use lexer::{Lexer, Spanned, Token};
+mod lexer;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub position: Position,
+}
+
+impl ParseError {
+    pub fn new(message: impl ToString, position: Position) -> Self {
+        let message = message.to_string();
+        Self { message, position }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Position { line, column } = self.position;
+        write!(f, "{line}:{column}: {}", self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// parses a module, numbering its nodes in pre-order.
+pub fn parse_module(source: &str) -> Result<Module, ParseError> {
+    let mut parser = Parser::new(source)?;
+    let mut module = parser.module()?;
+    parser.expect(Token::Eof)?;
+    module.assign_ids();
+    Ok(module)
+}
+
+/// parses a single expression, numbering its nodes in pre-order.
+pub fn parse_expr(source: &str) -> Result<Node, ParseError> {
+    let mut parser = Parser::new(source)?;
+    let mut node = parser.expr()?;
+    parser.expect(Token::Eof)?;
+    node.assign_ids();
+    Ok(*node)
+}
+
+const KEYWORDS: &[&str] = &[
+    "fn", "import", "export", "if", "then", "else", "loop", "return", "break", "true", "false",
+    "and", "or", "not", "let", "const", "global",
+];
+
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.contains(&word)
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    cursor: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self, ParseError> {
+        let tokens = Lexer::new(source).tokenize()?;
+        Ok(Self { tokens, cursor: 0 })
+    }
+
+    fn peek(&self) -> &Token {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, offset: usize) -> &Token {
+        let index = (self.cursor + offset).min(self.tokens.len() - 1);
+        &self.tokens[index].token
+    }
+
+    fn position(&self) -> Position {
+        self.tokens[self.cursor].position
+    }
+
+    fn span_from(&self, start: Position) -> Span {
+        let end = match self.cursor {
+            0 => start,
+            cursor => self.tokens[cursor - 1].end,
+        };
+        Span { start, end }
+    }
+
+    fn bump(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token != Token::Eof {
+            self.cursor += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == token;
+        if found {
+            self.bump();
+        }
+        found
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(ident) if ident == keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.bump();
+        }
+        found
+    }
+
+    fn error<T>(&self, expected: &str) -> Result<T, ParseError> {
+        let found = self.peek().describe();
+        let message = format!("expected {expected}, found {found}");
+        Err(ParseError::new(message, self.position()))
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), ParseError> {
+        if self.eat(&token) {
+            Ok(())
+        } else {
+            self.error(&token.describe())
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.error(&format!("'{keyword}'"))
+        }
+    }
+
+    fn name(&mut self) -> Result<Name, ParseError> {
+        match self.peek() {
+            Token::Ident(ident) if !is_keyword(ident) => {
+                let ident = ident.clone();
+                self.bump();
+                Ok(ident.into())
+            }
+            _ => self.error("identifier"),
+        }
+    }
+
+    /// parses `item (sep item)* sep?` up to and including `close`.
+    fn list<T>(
+        &mut self,
+        close: Token,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut result = vec![];
+        while !self.eat(&close) {
+            result.push(item(self)?);
+            if !self.eat(&Token::Comma) {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    fn module(&mut self) -> Result<Module, ParseError> {
+        let mut items = vec![];
+        while self.peek() != &Token::Eof {
+            items.push(self.top_level()?);
+        }
+        Ok(Module { items })
+    }
+
+    fn top_level(&mut self) -> Result<TopLevel, ParseError> {
+        if self.eat_keyword("import") {
+            let mut segments = vec![self.name()?];
+            loop {
+                self.expect(Token::PathSep)?;
+                if self.eat(&Token::LBrace) {
+                    break;
+                }
+                segments.push(self.name()?);
+            }
+            let module_name = join_segments(segments);
+            let items = self.list(Token::RBrace, Self::name)?;
+            self.expect(Token::Semicolon)?;
+            Ok(TopLevel::Import(Import { module_name, items }))
+        } else if self.eat_keyword("export") {
+            self.expect(Token::LBrace)?;
+            let items = self.list(Token::RBrace, Self::name)?;
+            self.expect(Token::Semicolon)?;
+            Ok(TopLevel::Export(Export { items }))
+        } else if self.is_keyword("fn") {
+            let start = self.position();
+            self.bump();
+            let name = self.name()?;
+            let (parameters, result, expressions) = self.function()?;
+            Ok(TopLevel::FnDef(FnDef {
+                meta: Meta::spanned(self.span_from(start)),
+                name,
+                parameters,
+                result,
+                expressions,
+            }))
+        } else if self.eat_keyword("const") {
+            Ok(TopLevel::Const(self.value_def()?))
+        } else if self.eat_keyword("global") {
+            Ok(TopLevel::Global(self.value_def()?))
+        } else {
+            self.error("'import', 'export', 'fn', 'const' or 'global'")
+        }
+    }
+
+    /// parses `name = value;`.
+    fn value_def(&mut self) -> Result<ValueDef, ParseError> {
+        let name = self.name()?;
+        self.expect(Token::Equal)?;
+        let value = self.expr()?;
+        self.expect(Token::Semicolon)?;
+        Ok(ValueDef { name, value })
+    }
+
+    /// parses `(parameters) -> result { body }`, the result being optional.
+    fn function(&mut self) -> Result<(Vec<Parameter>, Option<Type>, Block), ParseError> {
+        self.expect(Token::LParen)?;
+        let parameters = self.list(Token::RParen, Self::parameter)?;
+        let result = match self.eat(&Token::Arrow) {
+            true => Some(self.ty()?),
+            false => None,
+        };
+        let expressions = self.block()?;
+        Ok((parameters, result, expressions))
+    }
+
+    fn parameter(&mut self) -> Result<Parameter, ParseError> {
+        let name = self.name()?;
+        let ty = match self.eat(&Token::Colon) {
+            true => Some(self.ty()?),
+            false => None,
+        };
+        Ok(Parameter { name, ty })
+    }
+
+    fn ty(&mut self) -> Result<Type, ParseError> {
+        let ty = match self.peek() {
+            Token::Ident(ident) => Type::from_name(ident),
+            _ => None,
+        };
+        match ty {
+            Some(ty) => {
+                self.bump();
+                Ok(ty)
+            }
+            None => self.error("type"),
+        }
+    }
+
+    fn block(&mut self) -> Result<Block, ParseError> {
+        self.expect(Token::LBrace)?;
+        let mut expressions = vec![];
+        while !self.eat(&Token::RBrace) {
+            expressions.push(self.expr()?);
+            if !self.eat(&Token::Semicolon) {
+                self.expect(Token::RBrace)?;
+                break;
+            }
+        }
+        Ok(Block { expressions })
+    }
+
+    fn expr(&mut self) -> Result<BExpr, ParseError> {
+        let start = self.position();
+        let expr = if self.eat_keyword("return") {
+            let expression = self.expr()?;
+            Expr::Return(Return { expression })
+        } else if self.eat_keyword("break") {
+            let expression = self.expr()?;
+            Expr::Break(Break { expression })
+        } else if self.eat_keyword("let") {
+            let variable_name = self.name()?;
+            self.expect(Token::Equal)?;
+            let value = self.expr()?;
+            Expr::Declaration(Declaration {
+                variable_name,
+                value,
+            })
+        } else if self.eat_keyword("loop") {
+            let body = self.expr()?;
+            Expr::Loop(Loop { body })
+        } else if self.eat_keyword("if") {
+            let condition = self.expr()?;
+            self.expect_keyword("then")?;
+            let true_case = self.expr()?;
+            self.expect_keyword("else")?;
+            let false_case = self.expr()?;
+            Expr::Condition(Condition {
+                condition,
+                true_case,
+                false_case,
+            })
+        } else {
+            let target = self.binary(1)?;
+            let position = self.position();
+            if !self.eat(&Token::Equal) {
+                return Ok(target);
+            }
+            let value = self.expr()?;
+            match target.expr {
+                Expr::Invoke(Invoke { variable_name }) => Expr::Assignment(Assignment {
+                    variable_name,
+                    value,
+                }),
+                Expr::Index(Index { target, index }) => Expr::IndexAssignment(IndexAssignment {
+                    target,
+                    index,
+                    value,
+                }),
+                Expr::Field(Field { target, field }) => Expr::FieldAssignment(FieldAssignment {
+                    target,
+                    field,
+                    value,
+                }),
+                _ => return Err(ParseError::new("invalid assignment target", position)),
+            }
+        };
+        Ok(self.node(expr, start))
+    }
+
+    fn node(&self, expr: Expr, start: Position) -> BExpr {
+        let meta = Meta::spanned(self.span_from(start));
+        Box::new(Node { meta, expr })
+    }
+
+    /// parses operations whose operators bind at least as tight as
+    /// `precedence`, grouping to the left.
+    fn binary(&mut self, precedence: u8) -> Result<BExpr, ParseError> {
+        let start = self.position();
+        let mut left = self.unary()?;
+        while let Some(operator) = binary_operator(self.peek()) {
+            if operator.precedence() < precedence {
+                break;
+            }
+            self.bump();
+            let right = self.binary(operator.precedence() + 1)?;
+            let expr = Expr::BinaryOp(BinaryOp {
+                operator,
+                left,
+                right,
+            });
+            left = self.node(expr, start);
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<BExpr, ParseError> {
+        let start = self.position();
+        let operator = match self.peek() {
+            // a minus sign directly followed by a number is a litteral.
+            Token::Minus if !matches!(self.peek_nth(1), Token::Integer(_) | Token::Float(_)) => {
+                UnaryOperator::Neg
+            }
+            Token::Ident(ident) if ident == "not" => UnaryOperator::Not,
+            _ => return self.primary(),
+        };
+        self.bump();
+        let operand = self.unary()?;
+        let expr = Expr::UnaryOp(UnaryOp { operator, operand });
+        Ok(self.node(expr, start))
+    }
+
+    /// parses an atom followed by any number of calls, indexings and field
+    /// accesses.
+    fn primary(&mut self) -> Result<BExpr, ParseError> {
+        let position = self.position();
+        let mut target = self.atom()?;
+        loop {
+            let expr = if self.eat(&Token::LParen) {
+                let arguments = self.list(Token::RParen, Self::expr)?;
+                Expr::Call(Call {
+                    function: target,
+                    arguments,
+                })
+            } else if self.eat(&Token::LBracket) {
+                let index = self.expr()?;
+                self.expect(Token::RBracket)?;
+                Expr::Index(Index { target, index })
+            } else if self.eat(&Token::Dot) {
+                let field = self.key()?;
+                Expr::Field(Field { target, field })
+            } else {
+                return Ok(target);
+            };
+            target = self.node(expr, position);
+        }
+    }
+
+    fn atom(&mut self) -> Result<BExpr, ParseError> {
+        let position = self.position();
+        let expr = match self.peek().clone() {
+            Token::LParen => {
+                self.bump();
+                let expr = self.expr()?;
+                self.expect(Token::RParen)?;
+                return Ok(expr);
+            }
+            Token::LBrace => Expr::Block(self.block()?),
+            Token::Minus => {
+                self.bump();
+                match self.bump() {
+                    Token::Integer(int) => Expr::Litteral(integer(-int, position)?.into()),
+                    Token::Float(flt) => Expr::Litteral((-flt).into()),
+                    _ => return Err(ParseError::new("expected number after '-'", position)),
+                }
+            }
+            Token::Integer(int) => {
+                self.bump();
+                Expr::Litteral(integer(int, position)?.into())
+            }
+            Token::Float(flt) => {
+                self.bump();
+                Expr::Litteral(flt.into())
+            }
+            Token::String(str) => {
+                self.bump();
+                Expr::Litteral(str.into())
+            }
+            Token::LBracket => {
+                self.bump();
+                let list = self.list(Token::RBracket, Self::expr)?;
+                Expr::Litteral(Litteral::List(list))
+            }
+            Token::Hash => {
+                self.bump();
+                self.expect(Token::LBrace)?;
+                let map = self.list(Token::RBrace, Self::map_entry)?;
+                Expr::Litteral(Litteral::Map(map))
+            }
+            Token::Ident(ident) if ident == "true" || ident == "false" => {
+                self.bump();
+                Expr::Litteral((ident == "true").into())
+            }
+            Token::Ident(ident) if ident == "fn" => {
+                self.bump();
+                let (parameters, result, expressions) = self.function()?;
+                Expr::Lambda(Lambda {
+                    parameters,
+                    result,
+                    expressions,
+                })
+            }
+            Token::Ident(_) => self.path_or_variable()?,
+            _ => return self.error("expression"),
+        };
+        Ok(self.node(expr, position))
+    }
+
+    /// parses a map key or field name, either an identifier or a string.
+    fn key(&mut self) -> Result<Name, ParseError> {
+        match self.peek().clone() {
+            Token::String(key) => {
+                self.bump();
+                Ok(key.into())
+            }
+            _ => self.name(),
+        }
+    }
+
+    fn map_entry(&mut self) -> Result<(Name, BExpr), ParseError> {
+        let key = self.key()?;
+        self.expect(Token::Colon)?;
+        let value = self.expr()?;
+        Ok((key, value))
+    }
+
+    fn path_or_variable(&mut self) -> Result<Expr, ParseError> {
+        let mut segments = vec![self.name()?];
+        while self.eat(&Token::PathSep) {
+            segments.push(self.name()?);
+        }
+        if segments.len() == 1 {
+            let variable_name = segments.pop().unwrap();
+            return Ok(Expr::Invoke(Invoke { variable_name }));
+        }
+        let item = segments.pop().unwrap();
+        let module = join_segments(segments);
+        let item_path = Path { module, item };
+        if !self.eat(&Token::LParen) {
+            return Ok(Expr::Item(Item { item_path }));
+        }
+        let arguments = self.list(Token::RParen, Self::expr)?;
+        Ok(Expr::FnCall(FnCall {
+            fn_path: item_path,
+            arguments,
+        }))
+    }
+}
+
+fn binary_operator(token: &Token) -> Option<BinaryOperator> {
+    let operator = match token {
+        Token::Plus => BinaryOperator::Add,
+        Token::Minus => BinaryOperator::Sub,
+        Token::Star => BinaryOperator::Mul,
+        Token::Slash => BinaryOperator::Div,
+        Token::Percent => BinaryOperator::Rem,
+        Token::EqualEqual => BinaryOperator::Eq,
+        Token::NotEqual => BinaryOperator::Ne,
+        Token::Less => BinaryOperator::Lt,
+        Token::LessEqual => BinaryOperator::Le,
+        Token::Greater => BinaryOperator::Gt,
+        Token::GreaterEqual => BinaryOperator::Ge,
+        Token::Ident(ident) if ident == "and" => BinaryOperator::And,
+        Token::Ident(ident) if ident == "or" => BinaryOperator::Or,
+        _ => return None,
+    };
+    Some(operator)
+}
+
+fn join_segments(segments: Vec<Name>) -> Name {
+    let segments: Vec<String> = segments.into_iter().map(String::from).collect();
+    segments.join("::").into()
+}
+
+fn integer(value: i64, position: Position) -> Result<i32, ParseError> {
+    value
+        .try_into()
+        .map_err(|_| ParseError::new(format!("integer {value} out of range"), position))
+}
+
+#[test]
+fn test_parse_module() {
+    use crate::ast::NodeId;
+
+    let source = r#"
+        // computes n!
+        import std::math::{mul, sub};
+        export { fact };
+
+        fn fact(n) {
+            if std::math::eq(n, 0) then 1 else {
+                m = math::sub(n, 1);
+                return math::mul(n, main::fact(m));
+            }
+        }
+
+        fn misc() {
+            loop break [1, -2.5, "a\"b", true, #{ x: 1, "y z": false }];
+        }
+    "#;
+    let module = parse_module(source).unwrap();
+    assert_eq!(module.items.len(), 4);
+    let fact = module.items[2].as_fndef().unwrap();
+    assert_eq!(fact.name, "fact".into());
+    assert_eq!(fact.parameters, vec!["n".into()]);
+    let Expr::Condition(condition) = &fact.expressions.expressions[0].expr else {
+        panic!("expected a condition");
+    };
+    let Expr::FnCall(call) = &condition.condition.expr else {
+        panic!("expected a call");
+    };
+    assert_eq!(call.fn_path.module, "std::math".into());
+    assert_eq!(call.fn_path.item, "eq".into());
+
+    let mut expr = parse_expr("[1, -2.5, \"a\\\"b\"]").unwrap();
+    assert_eq!(expr.meta.id, Some(NodeId(1)));
+    let Expr::Litteral(Litteral::List(items)) = &expr.expr else {
+        panic!("expected a list");
+    };
+    let start = Position { line: 1, column: 5 };
+    let end = Position { line: 1, column: 9 };
+    assert_eq!(items[1].meta.id, Some(NodeId(3)));
+    assert_eq!(items[1].meta.span, Some(Span { start, end }));
+    expr.for_each_meta_mut(&mut |meta| *meta = Meta::default());
+    let expected = Expr::Litteral(Litteral::List(vec![
+        Expr::Litteral(1.into()).boxed(),
+        Expr::Litteral((-2.5).into()).boxed(),
+        Expr::Litteral("a\"b".into()).boxed(),
+    ]));
+    assert_eq!(expr.expr, expected);
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = parse_module("fn main() {\n    x = ;\n}").unwrap_err();
+    assert_eq!(error.position, Position { line: 2, column: 9 });
+    assert_eq!(error.to_string(), "2:9: expected expression, found ';'");
+
+    let error = parse_expr("\"abc").unwrap_err();
+    assert_eq!(error.to_string(), "1:1: unterminated string");
+
+    let error = parse_expr("a b").unwrap_err();
+    assert_eq!(error.to_string(), "1:3: expected end of input, found 'b'");
+
+    let error = parse_expr("f(x) = 1").unwrap_err();
+    assert_eq!(error.to_string(), "1:6: invalid assignment target");
+
+    let error = parse_expr("let x.y = 1").unwrap_err();
+    assert_eq!(error.to_string(), "1:6: expected '=', found '.'");
+
+    let error = parse_module("const LIMIT = 1").unwrap_err();
+    assert_eq!(error.to_string(), "1:16: expected ';', found end of input");
+
+    let error = parse_module("let x = 1;").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "1:1: expected 'import', 'export', 'fn', 'const' or 'global', found 'let'"
+    );
+}
@@ -0,0 +1,265 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::ast::Position;
+
+use super::ParseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Ident(String),
+    String(String),
+    Integer(i64),
+    Float(f32),
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Comma,
+    Semicolon,
+    Colon,
+    PathSep,
+    Dot,
+    Equal,
+    EqualEqual,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Hash,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Arrow,
+    Eof,
+}
+
+impl Token {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Ident(name) => format!("'{name}'"),
+            Self::String(_) => "string".into(),
+            Self::Integer(_) => "integer".into(),
+            Self::Float(_) => "float".into(),
+            Self::LParen => "'('".into(),
+            Self::RParen => "')'".into(),
+            Self::LBrace => "'{'".into(),
+            Self::RBrace => "'}'".into(),
+            Self::LBracket => "'['".into(),
+            Self::RBracket => "']'".into(),
+            Self::Comma => "','".into(),
+            Self::Semicolon => "';'".into(),
+            Self::Colon => "':'".into(),
+            Self::PathSep => "'::'".into(),
+            Self::Dot => "'.'".into(),
+            Self::Equal => "'='".into(),
+            Self::EqualEqual => "'=='".into(),
+            Self::NotEqual => "'!='".into(),
+            Self::Less => "'<'".into(),
+            Self::LessEqual => "'<='".into(),
+            Self::Greater => "'>'".into(),
+            Self::GreaterEqual => "'>='".into(),
+            Self::Hash => "'#'".into(),
+            Self::Plus => "'+'".into(),
+            Self::Minus => "'-'".into(),
+            Self::Star => "'*'".into(),
+            Self::Slash => "'/'".into(),
+            Self::Percent => "'%'".into(),
+            Self::Arrow => "'->'".into(),
+            Self::Eof => "end of input".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Spanned {
+    pub token: Token,
+    pub position: Position,
+    pub end: Position,
+}
+
+pub struct Lexer<'s> {
+    chars: Peekable<Chars<'s>>,
+    position: Position,
+}
+
+impl<'s> Lexer<'s> {
+    pub fn new(source: &'s str) -> Self {
+        let chars = source.chars().peekable();
+        let position = Position { line: 1, column: 1 };
+        Self { chars, position }
+    }
+
+    pub fn tokenize(mut self) -> Result<Vec<Spanned>, ParseError> {
+        let mut tokens = vec![];
+        loop {
+            self.skip_trivia();
+            let position = self.position;
+            let token = self.next_token()?;
+            let end = self.position;
+            let is_eof = token == Token::Eof;
+            tokens.push(Spanned {
+                token,
+                position,
+                end,
+            });
+            if is_eof {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let next = self.chars.next()?;
+        if next == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(next)
+    }
+
+    /// consumes the next character when it is `expected`.
+    fn eat(&mut self, expected: char) -> bool {
+        let found = self.chars.peek() == Some(&expected);
+        if found {
+            self.bump();
+        }
+        found
+    }
+
+    fn skip_trivia(&mut self) {
+        while let Some(&next) = self.chars.peek() {
+            if next.is_whitespace() {
+                self.bump();
+            } else if next == '/' && self.chars.clone().nth(1) == Some('/') {
+                while !matches!(self.chars.peek(), None | Some('\n')) {
+                    self.bump();
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, ParseError> {
+        let position = self.position;
+        let next = match self.bump() {
+            Some(next) => next,
+            None => return Ok(Token::Eof),
+        };
+        let token = match next {
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ',' => Token::Comma,
+            ';' => Token::Semicolon,
+            '.' => Token::Dot,
+            '=' if self.eat('=') => Token::EqualEqual,
+            '=' => Token::Equal,
+            '!' if self.eat('=') => Token::NotEqual,
+            '<' if self.eat('=') => Token::LessEqual,
+            '<' => Token::Less,
+            '>' if self.eat('=') => Token::GreaterEqual,
+            '>' => Token::Greater,
+            '#' => Token::Hash,
+            '+' => Token::Plus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '%' => Token::Percent,
+            '-' if self.eat('>') => Token::Arrow,
+            '-' => Token::Minus,
+            ':' if self.eat(':') => Token::PathSep,
+            ':' => Token::Colon,
+            '"' => self.string(position)?,
+            c if c.is_ascii_digit() => self.number(c, position)?,
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(&c) = self.chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    ident.push(c);
+                    self.bump();
+                }
+                Token::Ident(ident)
+            }
+            c => {
+                return Err(ParseError::new(
+                    format!("unexpected character '{c}'"),
+                    position,
+                ))
+            }
+        };
+        Ok(token)
+    }
+
+    fn string(&mut self, position: Position) -> Result<Token, ParseError> {
+        let mut result = String::new();
+        loop {
+            let next = self
+                .bump()
+                .ok_or_else(|| ParseError::new("unterminated string", position))?;
+            match next {
+                '"' => return Ok(Token::String(result)),
+                '\\' => {
+                    let escape_position = self.position;
+                    let escaped = match self.bump() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('0') => '\0',
+                        Some('\\') => '\\',
+                        Some('"') => '"',
+                        _ => return Err(ParseError::new("invalid escape", escape_position)),
+                    };
+                    result.push(escaped);
+                }
+                c => result.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self, first: char, position: Position) -> Result<Token, ParseError> {
+        let mut text = first.to_string();
+        let mut is_float = false;
+        while let Some(&c) = self.chars.peek() {
+            let is_fraction = c == '.'
+                && !is_float
+                && matches!(self.chars.clone().nth(1), Some(d) if d.is_ascii_digit());
+            let is_exponent = c == 'e' || c == 'E';
+            if is_exponent {
+                is_float = true;
+                text.push(c);
+                self.bump();
+                if let Some(&sign @ ('-' | '+')) = self.chars.peek() {
+                    text.push(sign);
+                    self.bump();
+                }
+            } else if is_fraction {
+                is_float = true;
+                text.push(c);
+                self.bump();
+            } else if c.is_ascii_digit() {
+                text.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        let invalid = || ParseError::new(format!("invalid number '{text}'"), position);
+        if is_float {
+            text.parse().map(Token::Float).map_err(|_| invalid())
+        } else {
+            text.parse().map(Token::Integer).map_err(|_| invalid())
+        }
+    }
+}
@@ -1,6 +1,201 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Primitive {
-    String(String),
-    Integer(i32),
-    Float(f32),
-    Bool(bool),
+    String,
+    Integer,
+    Float,
+    Bool,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Type {
+    Primitive(Primitive),
+    List,
+    Object,
+    Function,
+    /// opaque object handed over by the host.
+    UserData,
+    None,
+    Any,
+}
+
+impl Type {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Primitive(Primitive::String) => "string",
+            Self::Primitive(Primitive::Integer) => "integer",
+            Self::Primitive(Primitive::Float) => "float",
+            Self::Primitive(Primitive::Bool) => "bool",
+            Self::List => "list",
+            Self::Object => "object",
+            Self::Function => "function",
+            Self::UserData => "userdata",
+            Self::None => "none",
+            Self::Any => "any",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let result = match name {
+            "string" => Primitive::String.into(),
+            "integer" => Primitive::Integer.into(),
+            "float" => Primitive::Float.into(),
+            "bool" => Primitive::Bool.into(),
+            "list" => Self::List,
+            "object" => Self::Object,
+            "function" => Self::Function,
+            "userdata" => Self::UserData,
+            "none" => Self::None,
+            "any" => Self::Any,
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// whether a value of type `other` can be used where `self` is expected,
+    /// integers being promoted where floats are.
+    pub fn accepts(&self, other: &Type) -> bool {
+        self == &Self::Any
+            || other == &Self::Any
+            || self == other
+            || (self == &Primitive::Float.into() && other == &Primitive::Integer.into())
+    }
+
+    /// smallest type covering both `self` and `other`.
+    pub fn join(self, other: Type) -> Type {
+        if self == other {
+            self
+        } else {
+            Self::Any
+        }
+    }
+}
+
+impl From<Primitive> for Type {
+    fn from(primitive: Primitive) -> Self {
+        Self::Primitive(primitive)
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// number of arguments a function accepts, `max` being `None` when it
+/// takes any number of trailing arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn exact(count: usize) -> Self {
+        Self {
+            min: count,
+            max: Some(count),
+        }
+    }
+
+    pub fn range(min: usize, max: usize) -> Self {
+        Self {
+            min,
+            max: Some(max),
+        }
+    }
+
+    pub fn at_least(min: usize) -> Self {
+        Self { min, max: None }
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+impl From<usize> for Arity {
+    fn from(count: usize) -> Self {
+        Self::exact(count)
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{max}"),
+            Some(max) => write!(f, "{} to {max}", self.min),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
+/// parameter types of a function, the last ones possibly being optional and
+/// followed by a variadic tail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub parameters: Vec<Type>,
+    /// number of leading parameters which must be given.
+    pub required: usize,
+    /// type of the trailing arguments after the parameters, if any.
+    pub rest: Option<Type>,
+    pub result: Type,
+}
+
+impl Signature {
+    pub fn new(parameters: Vec<Type>, result: Type) -> Self {
+        let required = parameters.len();
+        Self {
+            parameters,
+            required,
+            rest: None,
+            result,
+        }
+    }
+
+    /// makes the last `count` parameters optional.
+    pub fn with_optional(mut self, count: usize) -> Self {
+        self.required = self.parameters.len().saturating_sub(count);
+        self
+    }
+
+    /// accepts any number of trailing arguments of the given type.
+    pub fn with_rest(mut self, ty: Type) -> Self {
+        self.rest = Some(ty);
+        self
+    }
+
+    /// signature accepting arguments of any type in the given number.
+    pub fn untyped(arity: impl Into<Arity>) -> Self {
+        let Arity { min, max } = arity.into();
+        let signature = Self::new(vec![Type::Any; max.unwrap_or(min)], Type::Any);
+        match max {
+            Some(max) => signature.with_optional(max - min),
+            None => signature.with_rest(Type::Any),
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        Arity {
+            min: self.required,
+            max: self.rest.is_none().then_some(self.parameters.len()),
+        }
+    }
+
+    /// expected type of the argument at an index.
+    pub fn parameter(&self, index: usize) -> Option<&Type> {
+        self.parameters.get(index).or(self.rest.as_ref())
+    }
+}
+
+/// signatures of the functions reachable from the checked module.
+pub type Signatures = HashMap<Path, Signature>;
+
+pub use checker::{check_module, TypeError, TypeErrorKind};
+mod checker;
@@ -0,0 +1,961 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::ast::{
+    Assignment, BExpr, BinaryOp, BinaryOperator, Block, Call, Declaration, Expr, FnCall, FnDef,
+    Item, Lambda, Litteral, Meta, Module, Name, Node, NodeId, Path, TopLevel, UnaryOp,
+    UnaryOperator, ValueDef,
+};
+
+use super::{Arity, Primitive, Signature, Signatures, Type};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeErrorKind {
+    Mismatch {
+        expected: Type,
+        found: Type,
+    },
+    ArityMismatch {
+        function: Path,
+        expected: Arity,
+        found: usize,
+    },
+    InvalidOperands {
+        operator: BinaryOperator,
+        left: Type,
+        right: Type,
+    },
+    InvalidOperand {
+        operator: UnaryOperator,
+        operand: Type,
+    },
+    UndefinedVariable(Name),
+    UnknownFunction(Path),
+    UnknownItem(Path),
+    ConstAssignment(Name),
+    BreakOutsideLoop,
+}
+
+impl Display for TypeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mismatch { expected, found } => {
+                write!(f, "type mismatch, expected {expected} but found {found}")
+            }
+            Self::ArityMismatch {
+                function: Path { module, item },
+                expected,
+                found,
+            } => write!(
+                f,
+                "function '{}::{}' expects {expected} arguments but received {found}",
+                module.0, item.0
+            ),
+            Self::InvalidOperands {
+                operator,
+                left,
+                right,
+            } => write!(
+                f,
+                "cannot apply '{}' to {left} and {right}",
+                operator.symbol()
+            ),
+            Self::InvalidOperand { operator, operand } => {
+                write!(f, "cannot apply '{}' to {operand}", operator.symbol())
+            }
+            Self::UndefinedVariable(Name(name)) => write!(f, "undefined variable '{name}'"),
+            Self::UnknownFunction(Path { module, item }) => {
+                write!(f, "unknown function '{}::{}'", module.0, item.0)
+            }
+            Self::UnknownItem(Path { module, item }) => {
+                write!(f, "unknown constant or global '{}::{}'", module.0, item.0)
+            }
+            Self::ConstAssignment(Name(name)) => write!(f, "cannot assign constant '{name}'"),
+            Self::BreakOutsideLoop => write!(f, "break outside of loop"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    pub kind: TypeErrorKind,
+    /// identity and location of the offending node.
+    pub meta: Meta,
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.meta {
+            Meta {
+                span: Some(span), ..
+            } => write!(
+                f,
+                "{}:{}: {}",
+                span.start.line, span.start.column, self.kind
+            ),
+            Meta {
+                id: Some(NodeId(id)),
+                ..
+            } => write!(f, "node #{id}: {}", self.kind),
+            _ => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+/// checks the module named `name`, calls into other modules being checked
+/// against `signatures` when they are known.
+pub fn check_module(name: &Name, module: &Module, signatures: &Signatures) -> Vec<TypeError> {
+    Checker::new(name, module, signatures).run()
+}
+
+/// upper bound on the number of inference passes over the module.
+const MAX_PASSES: usize = 16;
+
+/// inferred type of an expression, `None` when it never yields a value
+/// (it returns, breaks or has not been inferred yet).
+type Inferred = Option<Type>;
+
+fn join(left: Inferred, right: Inferred) -> Inferred {
+    match (left, right) {
+        (None, other) | (other, None) => other,
+        (Some(left), Some(right)) => Some(left.join(right)),
+    }
+}
+
+struct Checker<'m> {
+    name: &'m Name,
+    signatures: &'m Signatures,
+    definitions: Vec<&'m FnDef>,
+    /// constants and globals along with whether they can be assigned.
+    values: Vec<(&'m ValueDef, bool)>,
+    imports: HashMap<&'m Name, Path>,
+    results: HashMap<&'m Name, Inferred>,
+    /// types of the constants and globals initialized so far.
+    items: HashMap<&'m Name, Inferred>,
+    variables: HashMap<&'m Name, HashMap<Name, Inferred>>,
+    /// variables of each initializer, keyed by the name of its item.
+    initializers: HashMap<&'m Name, HashMap<Name, Inferred>>,
+    /// variables of each lambda, keyed by its address in the checked module.
+    lambdas: HashMap<*const Lambda, HashMap<Name, Inferred>>,
+    scope: HashMap<Name, Inferred>,
+    returns: Inferred,
+    expected_result: Option<Type>,
+    loops: Vec<Inferred>,
+    errors: Vec<TypeError>,
+    changed: bool,
+}
+
+impl<'m> Checker<'m> {
+    fn new(name: &'m Name, module: &'m Module, signatures: &'m Signatures) -> Self {
+        let mut definitions = vec![];
+        let mut values = vec![];
+        let mut imports = HashMap::new();
+        for item in &module.items {
+            match item {
+                TopLevel::FnDef(fndef) => definitions.push(fndef),
+                TopLevel::Const(definition) => values.push((definition, false)),
+                TopLevel::Global(definition) => values.push((definition, true)),
+                TopLevel::Import(import) => {
+                    for item in &import.items {
+                        let module = import.module_name.clone();
+                        let path = Path {
+                            module,
+                            item: item.clone(),
+                        };
+                        imports.insert(item, path);
+                    }
+                }
+                TopLevel::Export(_) => (),
+            }
+        }
+        Self {
+            name,
+            signatures,
+            definitions,
+            values,
+            imports,
+            results: HashMap::new(),
+            items: HashMap::new(),
+            variables: HashMap::new(),
+            initializers: HashMap::new(),
+            lambdas: HashMap::new(),
+            scope: HashMap::new(),
+            returns: None,
+            expected_result: None,
+            loops: vec![],
+            errors: vec![],
+            changed: false,
+        }
+    }
+
+    /// infers function results and variable types until they stabilize,
+    /// errors of the last pass being the reported ones.
+    fn run(mut self) -> Vec<TypeError> {
+        for _ in 0..MAX_PASSES {
+            self.errors.clear();
+            self.changed = false;
+            self.initializers();
+            for fndef in self.definitions.clone() {
+                self.function(fndef);
+            }
+            if !self.changed {
+                break;
+            }
+        }
+        self.errors
+    }
+
+    fn error(&mut self, kind: TypeErrorKind, meta: &Meta) {
+        let meta = *meta;
+        self.errors.push(TypeError { kind, meta });
+    }
+
+    fn expect(&mut self, expected: Type, found: &Inferred, meta: &Meta) {
+        if let Some(found) = found {
+            if !expected.accepts(found) {
+                let found = found.clone();
+                self.error(TypeErrorKind::Mismatch { expected, found }, meta);
+            }
+        }
+    }
+
+    /// infers the constants and globals in order, each initializer only
+    /// seeing the items defined before it.
+    fn initializers(&mut self) {
+        let previous = std::mem::take(&mut self.items);
+        for (definition, _) in self.values.clone() {
+            self.scope = self
+                .initializers
+                .remove(&definition.name)
+                .unwrap_or_default();
+            self.returns = None;
+            self.expected_result = None;
+            let value = self.node(&definition.value);
+            let inferred = join(value, self.returns.take());
+            if previous.get(&definition.name) != Some(&inferred) {
+                self.changed = true;
+            }
+            self.items.insert(&definition.name, inferred);
+            let scope = std::mem::take(&mut self.scope);
+            self.initializers.insert(&definition.name, scope);
+        }
+    }
+
+    /// whether a name stands for a global of the module, `false` for a
+    /// constant and `None` for neither.
+    fn mutability(&self, name: &Name) -> Option<bool> {
+        let (_, mutable) = self.values.iter().find(|(d, _)| &d.name == name)?;
+        Some(*mutable)
+    }
+
+    /// joins the type of a value into a variable of the scope.
+    fn declare(&mut self, name: &Name, value: Inferred) -> Inferred {
+        let previous = self.scope.get(name).cloned();
+        let joined = join(previous.clone().flatten(), value.clone());
+        if previous != Some(joined.clone()) {
+            self.changed = true;
+            self.scope.insert(name.clone(), joined);
+        }
+        value
+    }
+
+    fn function(&mut self, fndef: &'m FnDef) {
+        let mut scope = self.variables.remove(&fndef.name).unwrap_or_default();
+        for parameter in &fndef.parameters {
+            let ty = parameter.ty.clone().unwrap_or(Type::Any);
+            scope.insert(parameter.name.clone(), Some(ty));
+        }
+        self.scope = scope;
+        self.returns = None;
+        self.expected_result = fndef.result.clone();
+        let last = self.body(&fndef.expressions, &fndef.meta);
+        let result = match &fndef.result {
+            Some(expected) => Some(expected.clone()),
+            None => join(last, self.returns.take()),
+        };
+        let previous = self.results.get(&fndef.name).cloned().flatten();
+        let result = join(previous.clone(), result);
+        if result != previous {
+            self.changed = true;
+        }
+        self.results.insert(&fndef.name, result);
+        let scope = std::mem::take(&mut self.scope);
+        self.variables.insert(&fndef.name, scope);
+    }
+
+    /// infers a function body, checking its last expression against the
+    /// expected result.
+    fn body(&mut self, body: &Block, meta: &Meta) -> Inferred {
+        let last = self.block(&body.expressions);
+        if let Some(expected) = self.expected_result.clone() {
+            let meta = match body.expressions.last() {
+                Some(last) => &last.meta,
+                None => meta,
+            };
+            self.expect(expected, &last, meta);
+        }
+        last
+    }
+
+    fn block(&mut self, expressions: &[BExpr]) -> Inferred {
+        let mut last = Some(Type::None);
+        let mut diverges = false;
+        for expr in expressions {
+            last = self.node(expr);
+            diverges |= last.is_none();
+        }
+        if diverges {
+            None
+        } else {
+            last
+        }
+    }
+
+    fn node(&mut self, node: &Node) -> Inferred {
+        let meta = &node.meta;
+        match &node.expr {
+            Expr::Block(block) => self.block(&block.expressions),
+            // variables are tracked per function whatever their block, so
+            // shadowing ones share the type of the variable they hide.
+            Expr::Assignment(Assignment {
+                variable_name,
+                value,
+            }) if !self.scope.contains_key(variable_name) => {
+                let value = self.node(value);
+                match self.mutability(variable_name) {
+                    Some(true) => value,
+                    Some(false) => {
+                        let name = variable_name.clone();
+                        self.error(TypeErrorKind::ConstAssignment(name), meta);
+                        value
+                    }
+                    None => self.declare(variable_name, value),
+                }
+            }
+            Expr::Assignment(Assignment {
+                variable_name,
+                value,
+            })
+            | Expr::Declaration(Declaration {
+                variable_name,
+                value,
+            }) => {
+                let value = self.node(value);
+                self.declare(variable_name, value)
+            }
+            // constants and globals are hidden by variables of the same name.
+            Expr::Invoke(invoke) => {
+                let name = &invoke.variable_name;
+                match self.scope.get(name).or_else(|| self.items.get(name)) {
+                    Some(inferred) => inferred.clone(),
+                    None => {
+                        let name = name.clone();
+                        self.error(TypeErrorKind::UndefinedVariable(name), meta);
+                        Some(Type::Any)
+                    }
+                }
+            }
+            Expr::Item(item) => self.item(item, meta),
+            Expr::Index(index) => self.index(&index.target, &index.index),
+            Expr::IndexAssignment(assignment) => {
+                let place = self.index(&assignment.target, &assignment.index);
+                let value = self.node(&assignment.value);
+                place.and(value)
+            }
+            Expr::Field(field) => self.field(&field.target),
+            Expr::FieldAssignment(assignment) => {
+                let place = self.field(&assignment.target);
+                let value = self.node(&assignment.value);
+                place.and(value)
+            }
+            Expr::Litteral(litteral) => self.litteral(litteral),
+            Expr::FnCall(fn_call) => self.fn_call(fn_call, meta),
+            Expr::Lambda(lambda) => self.lambda(lambda, meta),
+            Expr::Call(call) => self.call(call),
+            Expr::BinaryOp(binary_op) => self.binary_op(binary_op, meta),
+            Expr::UnaryOp(unary_op) => self.unary_op(unary_op, meta),
+            Expr::Condition(condition) => {
+                let tested = self.node(&condition.condition);
+                self.expect(Primitive::Bool.into(), &tested, &condition.condition.meta);
+                let true_case = self.node(&condition.true_case);
+                let false_case = self.node(&condition.false_case);
+                join(true_case, false_case)
+            }
+            Expr::Loop(loop_) => {
+                self.loops.push(None);
+                self.node(&loop_.body);
+                self.loops.pop().flatten()
+            }
+            Expr::Return(return_) => {
+                let returned = self.node(&return_.expression);
+                if let Some(expected) = self.expected_result.clone() {
+                    self.expect(expected, &returned, &return_.expression.meta);
+                }
+                self.returns = join(self.returns.take(), returned);
+                None
+            }
+            Expr::Break(break_) => {
+                let broken = self.node(&break_.expression);
+                match self.loops.last_mut() {
+                    Some(loop_) => *loop_ = join(loop_.take(), broken),
+                    None => self.error(TypeErrorKind::BreakOutsideLoop, meta),
+                }
+                None
+            }
+        }
+    }
+
+    /// checks the body of a lambda in a scope of its own, seeded with the
+    /// variables it captures.
+    fn lambda(&mut self, lambda: &Lambda, meta: &Meta) -> Inferred {
+        let key = lambda as *const Lambda;
+        let mut scope = self.scope.clone();
+        scope.extend(self.lambdas.remove(&key).unwrap_or_default());
+        for parameter in &lambda.parameters {
+            let ty = parameter.ty.clone().unwrap_or(Type::Any);
+            scope.insert(parameter.name.clone(), Some(ty));
+        }
+        let outer_scope = std::mem::replace(&mut self.scope, scope);
+        let outer_returns = self.returns.take();
+        let outer_result = std::mem::replace(&mut self.expected_result, lambda.result.clone());
+        let outer_loops = std::mem::take(&mut self.loops);
+        self.body(&lambda.expressions, meta);
+        let scope = std::mem::replace(&mut self.scope, outer_scope);
+        self.lambdas.insert(key, scope);
+        self.returns = outer_returns;
+        self.expected_result = outer_result;
+        self.loops = outer_loops;
+        Some(Type::Function)
+    }
+
+    fn call(&mut self, call: &Call) -> Inferred {
+        let function = match &call.function.expr {
+            // method of a host object.
+            Expr::Field(field) => {
+                let target = self.node(&field.target);
+                if target != Some(Type::UserData) {
+                    self.expect(Type::Object, &target, &field.target.meta);
+                }
+                target.map(|_| Type::Any)
+            }
+            _ => self.node(&call.function),
+        };
+        self.expect(Type::Function, &function, &call.function.meta);
+        let arguments: Vec<_> = call.arguments.iter().map(|a| self.node(a)).collect();
+        if function.is_none() || arguments.iter().any(Option::is_none) {
+            None
+        } else {
+            Some(Type::Any)
+        }
+    }
+
+    /// type of a constant or global, those of other modules being unknown.
+    fn item(&mut self, item: &Item, meta: &Meta) -> Inferred {
+        let path = &item.item_path;
+        if &path.module != self.name {
+            return Some(Type::Any);
+        }
+        match self.items.get(&path.item) {
+            Some(inferred) => inferred.clone(),
+            None => {
+                self.error(TypeErrorKind::UnknownItem(path.clone()), meta);
+                Some(Type::Any)
+            }
+        }
+    }
+
+    fn index(&mut self, target: &Node, index: &Node) -> Inferred {
+        let container = self.node(target);
+        let key = self.node(index);
+        match &container {
+            Some(Type::List) => self.expect(Primitive::Integer.into(), &key, &index.meta),
+            Some(Type::Object) => self.expect(Primitive::String.into(), &key, &index.meta),
+            Some(Type::Any) | None => (),
+            Some(found) => {
+                let found = found.clone();
+                let expected = Type::List;
+                self.error(TypeErrorKind::Mismatch { expected, found }, &target.meta);
+            }
+        }
+        container.and(key).map(|_| Type::Any)
+    }
+
+    fn field(&mut self, target: &Node) -> Inferred {
+        let container = self.node(target);
+        self.expect(Type::Object, &container, &target.meta);
+        container.map(|_| Type::Any)
+    }
+
+    fn binary_op(&mut self, binary_op: &BinaryOp, meta: &Meta) -> Inferred {
+        let left = self.node(&binary_op.left);
+        let right = self.node(&binary_op.right);
+        let (Some(left), Some(right)) = (left, right) else {
+            return None;
+        };
+        let operator = binary_op.operator;
+        let result = binary_result(operator, &left, &right);
+        if result.is_none() {
+            let kind = TypeErrorKind::InvalidOperands {
+                operator,
+                left,
+                right,
+            };
+            self.error(kind, meta);
+        }
+        Some(result.unwrap_or(Type::Any))
+    }
+
+    fn unary_op(&mut self, unary_op: &UnaryOp, meta: &Meta) -> Inferred {
+        let operand = self.node(&unary_op.operand)?;
+        let operator = unary_op.operator;
+        let result = match (operator, &operand) {
+            (UnaryOperator::Neg, Type::Primitive(Primitive::Integer | Primitive::Float)) => {
+                Some(operand.clone())
+            }
+            (UnaryOperator::Neg, Type::Any) => Some(Type::Any),
+            (UnaryOperator::Not, Type::Primitive(Primitive::Bool) | Type::Any) => {
+                Some(Primitive::Bool.into())
+            }
+            _ => None,
+        };
+        if result.is_none() {
+            self.error(TypeErrorKind::InvalidOperand { operator, operand }, meta);
+        }
+        Some(result.unwrap_or(Type::Any))
+    }
+
+    fn litteral(&mut self, litteral: &Litteral) -> Inferred {
+        let result = match litteral {
+            Litteral::String(_) => Primitive::String.into(),
+            Litteral::Integer(_) => Primitive::Integer.into(),
+            Litteral::Float(_) => Primitive::Float.into(),
+            Litteral::Bool(_) => Primitive::Bool.into(),
+            Litteral::List(list) => {
+                for item in list {
+                    self.node(item);
+                }
+                Type::List
+            }
+            Litteral::Map(map) => {
+                for (_, item) in map {
+                    self.node(item);
+                }
+                Type::Object
+            }
+        };
+        Some(result)
+    }
+
+    /// signature of the called function along with its inferred result,
+    /// `None` when it cannot be known.
+    fn signature(&mut self, path: &Path, meta: &Meta) -> Option<(Signature, Inferred)> {
+        if &path.module != self.name {
+            let signature = self.signatures.get(path)?.clone();
+            let result = Some(signature.result.clone());
+            return Some((signature, result));
+        }
+        if let Some(fndef) = self.definitions.iter().find(|f| f.name == path.item) {
+            let signature = fndef.signature();
+            let result = self.results.get(&fndef.name).cloned().flatten();
+            return Some((signature, result));
+        }
+        if let Some(target) = self.imports.get(&path.item) {
+            let signature = self.signatures.get(target)?.clone();
+            let result = Some(signature.result.clone());
+            return Some((signature, result));
+        }
+        self.error(TypeErrorKind::UnknownFunction(path.clone()), meta);
+        None
+    }
+
+    fn fn_call(&mut self, fn_call: &FnCall, meta: &Meta) -> Inferred {
+        let arguments: Vec<_> = fn_call.arguments.iter().map(|a| self.node(a)).collect();
+        let diverges = arguments.iter().any(Option::is_none);
+        let path = &fn_call.fn_path;
+        let Some((signature, result)) = self.signature(path, meta) else {
+            return if diverges { None } else { Some(Type::Any) };
+        };
+        if !signature.arity().accepts(arguments.len()) {
+            let kind = TypeErrorKind::ArityMismatch {
+                function: path.clone(),
+                expected: signature.arity(),
+                found: arguments.len(),
+            };
+            self.error(kind, meta);
+        } else {
+            for (index, (argument, node)) in arguments.iter().zip(&fn_call.arguments).enumerate() {
+                if let Some(parameter) = signature.parameter(index) {
+                    self.expect(parameter.clone(), argument, &node.meta);
+                }
+            }
+        }
+        if diverges {
+            None
+        } else {
+            result
+        }
+    }
+}
+
+/// type of an operation on values of the given types, `None` when the
+/// operands are invalid.
+fn binary_result(operator: BinaryOperator, left: &Type, right: &Type) -> Option<Type> {
+    use BinaryOperator::*;
+    use Primitive::{Bool, Float, Integer, String};
+
+    let is = |ty: &Type, primitive: Primitive| ty == &primitive.into() || ty == &Type::Any;
+    let numeric = |ty: &Type| is(ty, Integer) || is(ty, Float);
+    let result: Type = match operator {
+        Eq | Ne => Bool.into(),
+        And | Or if is(left, Bool) && is(right, Bool) => Bool.into(),
+        Lt | Le | Gt | Ge
+            if (numeric(left) && numeric(right)) || (is(left, String) && is(right, String)) =>
+        {
+            Bool.into()
+        }
+        Add | Sub | Mul | Div | Rem if numeric(left) && numeric(right) => {
+            if left == &Float.into() || right == &Float.into() {
+                Float.into()
+            } else if left == &Integer.into() && right == &Integer.into() {
+                Integer.into()
+            } else {
+                Type::Any
+            }
+        }
+        Add if is(left, String) && is(right, String) => String.into(),
+        _ => return None,
+    };
+    Some(result)
+}
+
+#[test]
+fn test_check_module() {
+    use crate::parser::parse_module;
+
+    let source = r#"
+        fn main() {
+            x = 1;
+            if x then std::print(x) else main::count(x, 2);
+            std::print("a", "b");
+            std::abs("nope");
+            loop { if true then break main::count(1) else 2 };
+            y
+        }
+
+        fn count(n) {
+            if std::eq(n, 0) then return 0 else 1;
+            main::count(std::abs(n))
+        }
+
+        fn flag() { break true }
+    "#;
+    let module = parse_module(source).unwrap();
+    let mut signatures = Signatures::new();
+    let path = |item: &str| Path {
+        module: "std".into(),
+        item: item.into(),
+    };
+    let integer: Type = Primitive::Integer.into();
+    let abs = Signature::new(vec![integer.clone()], integer.clone());
+    signatures.insert(path("abs"), abs);
+    signatures.insert(path("print"), Signature::new(vec![Type::Any], Type::None));
+    signatures.insert(path("eq"), Signature::untyped(2));
+
+    let errors = check_module(&"main".into(), &module, &signatures);
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    let main = |item: &str| Path {
+        module: "main".into(),
+        item: item.into(),
+    };
+    assert_eq!(
+        kinds,
+        vec![
+            TypeErrorKind::Mismatch {
+                expected: Primitive::Bool.into(),
+                found: integer.clone(),
+            },
+            TypeErrorKind::ArityMismatch {
+                function: main("count"),
+                expected: 1.into(),
+                found: 2,
+            },
+            TypeErrorKind::ArityMismatch {
+                function: path("print"),
+                expected: 1.into(),
+                found: 2,
+            },
+            TypeErrorKind::Mismatch {
+                expected: integer,
+                found: Primitive::String.into(),
+            },
+            TypeErrorKind::UndefinedVariable("y".into()),
+            TypeErrorKind::BreakOutsideLoop,
+        ]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "4:16: type mismatch, expected bool but found integer"
+    );
+}
+
+#[test]
+fn test_check_variadic() {
+    use crate::parser::parse_module;
+
+    let source = r#"
+        fn main() {
+            std::format("{} {}", 1, 2);
+            std::format();
+            std::format(1);
+            std::slice("ab", 1);
+            std::slice("ab", 1, 2, 3)
+        }
+    "#;
+    let module = parse_module(source).unwrap();
+    let mut signatures = Signatures::new();
+    let path = |item: &str| Path {
+        module: "std".into(),
+        item: item.into(),
+    };
+    let string: Type = Primitive::String.into();
+    let integer: Type = Primitive::Integer.into();
+    let format = Signature::new(vec![string.clone()], string.clone()).with_rest(Type::Any);
+    let slice = Signature::new(
+        vec![string.clone(), integer.clone(), integer],
+        string.clone(),
+    )
+    .with_optional(1);
+    signatures.insert(path("format"), format);
+    signatures.insert(path("slice"), slice);
+
+    let errors = check_module(&"main".into(), &module, &signatures);
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TypeErrorKind::ArityMismatch {
+                function: path("format"),
+                expected: Arity::at_least(1),
+                found: 0,
+            },
+            TypeErrorKind::Mismatch {
+                expected: string,
+                found: Primitive::Integer.into(),
+            },
+            TypeErrorKind::ArityMismatch {
+                function: path("slice"),
+                expected: Arity::range(2, 3),
+                found: 4,
+            },
+        ]
+    );
+    assert_eq!(
+        errors[0].kind.to_string(),
+        "function 'std::format' expects at least 1 arguments but received 0"
+    );
+}
+
+#[test]
+fn test_check_user_data() {
+    use crate::parser::parse_module;
+
+    let source = r#"
+        fn main(canvas: userdata) {
+            canvas.resize(3);
+            canvas.width
+        }
+    "#;
+    let module = parse_module(source).unwrap();
+    let errors = check_module(&"main".into(), &module, &Signatures::new());
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![TypeErrorKind::Mismatch {
+            expected: Type::Object,
+            found: Type::UserData,
+        }]
+    );
+}
+
+#[test]
+fn test_check_items() {
+    use crate::parser::parse_module;
+
+    let source = r#"
+        const NAME = "counter" + main::STEP;
+        const STEP = 1;
+        global count = STEP;
+
+        fn next() {
+            count = count + main::STEP;
+            NAME = "other";
+            -NAME;
+            main::missing;
+            config::LIMIT
+        }
+    "#;
+    let module = parse_module(source).unwrap();
+    let errors = check_module(&"main".into(), &module, &Signatures::new());
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    let path = |item: &str| Path {
+        module: "main".into(),
+        item: item.into(),
+    };
+    assert_eq!(
+        kinds,
+        vec![
+            TypeErrorKind::UnknownItem(path("STEP")),
+            TypeErrorKind::ConstAssignment("NAME".into()),
+            TypeErrorKind::InvalidOperand {
+                operator: UnaryOperator::Neg,
+                operand: Primitive::String.into(),
+            },
+            TypeErrorKind::UnknownItem(path("missing")),
+        ]
+    );
+}
+
+#[test]
+fn test_check_annotations() {
+    use crate::parser::parse_module;
+
+    let source = r#"
+        fn main() { main::half("a") }
+        fn half(n: integer) -> integer { if true then return "b" else n; 1.5 }
+    "#;
+    let module = parse_module(source).unwrap();
+    let errors = check_module(&"main".into(), &module, &Signatures::new());
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    let integer: Type = Primitive::Integer.into();
+    assert_eq!(
+        kinds,
+        vec![
+            TypeErrorKind::Mismatch {
+                expected: integer.clone(),
+                found: Primitive::String.into(),
+            },
+            TypeErrorKind::Mismatch {
+                expected: integer.clone(),
+                found: Primitive::String.into(),
+            },
+            TypeErrorKind::Mismatch {
+                expected: integer,
+                found: Primitive::Float.into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_check_lambdas() {
+    use crate::parser::parse_module;
+
+    let source = r#"
+        fn main() {
+            n = 1;
+            add = fn(x: integer) -> integer { y = x; return "a"; n };
+            add(2);
+            n(3);
+            fn() { break 1 }
+        }
+    "#;
+    let module = parse_module(source).unwrap();
+    let errors = check_module(&"main".into(), &module, &Signatures::new());
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TypeErrorKind::Mismatch {
+                expected: Primitive::Integer.into(),
+                found: Primitive::String.into(),
+            },
+            TypeErrorKind::Mismatch {
+                expected: Type::Function,
+                found: Primitive::Integer.into(),
+            },
+            TypeErrorKind::BreakOutsideLoop,
+        ]
+    );
+}
+
+#[test]
+fn test_check_places() {
+    use crate::parser::parse_module;
+
+    let source = r#"
+        fn main() {
+            list = [1, 2];
+            object = #{ x: 1 };
+            list[0] = object.x;
+            list["a"];
+            object[0];
+            list.x = 1;
+            n = 1;
+            n[0]
+        }
+    "#;
+    let module = parse_module(source).unwrap();
+    let errors = check_module(&"main".into(), &module, &Signatures::new());
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TypeErrorKind::Mismatch {
+                expected: Primitive::Integer.into(),
+                found: Primitive::String.into(),
+            },
+            TypeErrorKind::Mismatch {
+                expected: Primitive::String.into(),
+                found: Primitive::Integer.into(),
+            },
+            TypeErrorKind::Mismatch {
+                expected: Type::Object,
+                found: Type::List,
+            },
+            TypeErrorKind::Mismatch {
+                expected: Type::List,
+                found: Primitive::Integer.into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_check_operators() {
+    use crate::parser::parse_module;
+
+    let source = r#"
+        fn main(x) {
+            a = 1 + 2 * 3;
+            b = a / 2.0;
+            c = "a" + x;
+            d = not (a < b) and c == "ab";
+            a + "b";
+            -"c";
+            not 1;
+            [a, b, c, d]
+        }
+    "#;
+    let module = parse_module(source).unwrap();
+    let errors = check_module(&"main".into(), &module, &Signatures::new());
+    let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TypeErrorKind::InvalidOperands {
+                operator: BinaryOperator::Add,
+                left: Primitive::Integer.into(),
+                right: Primitive::String.into(),
+            },
+            TypeErrorKind::InvalidOperand {
+                operator: UnaryOperator::Neg,
+                operand: Primitive::String.into(),
+            },
+            TypeErrorKind::InvalidOperand {
+                operator: UnaryOperator::Not,
+                operand: Primitive::Integer.into(),
+            },
+        ]
+    );
+}
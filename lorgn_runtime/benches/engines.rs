@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+use lorgn_lang::parser::{parse_expr, parse_module};
+use lorgn_runtime::{Engine, Module, Runtime};
+
+const SOURCE: &str = r#"
+    export { fib, sum, closures, objects };
+
+    fn fib(n) { if n < 2 then n else main::fib(n - 1) + main::fib(n - 2) }
+    fn sum(n) {
+        total = 0;
+        i = 0;
+        loop { if i == n then break total else { total = total + i; i = i + 1 } }
+    }
+    fn closures(n) {
+        add = fn(a, b) { a + b };
+        total = 0;
+        i = 0;
+        loop { if i == n then break total else { total = add(total, i); i = i + 1 } }
+    }
+    fn objects(n) {
+        point = #{ x: 0, y: 0 };
+        i = 0;
+        loop {
+            if i == n then break point.x + point.y else {
+                point.x = point.x + 1;
+                point.y = point.y + 2;
+                i = i + 1
+            }
+        }
+    }
+"#;
+
+const BENCHES: [(&str, &str); 4] = [
+    ("fib", "main::fib(20)"),
+    ("sum", "main::sum(100000)"),
+    ("closures", "main::closures(50000)"),
+    ("objects", "main::objects(50000)"),
+];
+
+const RUNS: u32 = 10;
+
+/// average time of an expression over several runs, after a first one
+/// compiling the functions.
+fn measure(engine: Engine, source: &str) -> Duration {
+    let mut runtime = Runtime::with_std();
+    runtime.set_engine(engine);
+    let module = Module::from_ast("main", parse_module(SOURCE).unwrap());
+    runtime.register(module).unwrap();
+    let expr = parse_expr(source).unwrap().expr;
+    let expected = runtime.evaluate(expr.clone()).unwrap();
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        assert_eq!(runtime.evaluate(expr.clone()).unwrap(), expected);
+    }
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    println!(
+        "{:<10} {:>14} {:>14} {:>8}",
+        "bench", "tree walker", "bytecode", "speedup"
+    );
+    for (name, source) in BENCHES {
+        let tree_walker = measure(Engine::TreeWalker, source);
+        let bytecode = measure(Engine::Bytecode, source);
+        let speedup = tree_walker.as_secs_f64() / bytecode.as_secs_f64();
+        println!("{name:<10} {tree_walker:>14.2?} {bytecode:>14.2?} {speedup:>7.2}x");
+    }
+}
@@ -0,0 +1,422 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use gc::Gc;
+
+use lorgn_lang::{
+    ast::Name,
+    typing::{Arity, Primitive, Signature, Type},
+};
+
+use crate::{ErrorKind, Function, RuntimeError, UserData, Value};
+
+/// rust type which can be read from a value.
+pub trait FromValue: Sized {
+    /// type of the values accepted.
+    fn expected_type() -> Type;
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError>;
+}
+
+/// rust type which can be turned into a value.
+pub trait IntoValue {
+    /// type of the values produced.
+    fn produced_type() -> Type;
+
+    fn into_value(self) -> Result<Value, RuntimeError>;
+
+    /// whether the values produced are newly built rather than handed back,
+    /// natives returning them being charged for their memory.
+    fn allocates() -> bool {
+        true
+    }
+}
+
+fn mismatch(expected: Type, found: &Value) -> RuntimeError {
+    ErrorKind::TypeMismatch {
+        expected: expected.name(),
+        found: found.type_name(),
+    }
+    .into()
+}
+
+impl FromValue for Value {
+    fn expected_type() -> Type {
+        Type::Any
+    }
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        Ok(value)
+    }
+}
+
+/// the value may already exist, natives building one account for it
+/// themselves.
+impl IntoValue for Value {
+    fn produced_type() -> Type {
+        Type::Any
+    }
+
+    fn into_value(self) -> Result<Value, RuntimeError> {
+        Ok(self)
+    }
+
+    fn allocates() -> bool {
+        false
+    }
+}
+
+impl FromValue for i32 {
+    fn expected_type() -> Type {
+        Primitive::Integer.into()
+    }
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match &value {
+            &Value::Integer(int) => Ok(int),
+            _ => Err(mismatch(Self::expected_type(), &value)),
+        }
+    }
+}
+
+fn out_of_range(value: i128, target: &'static str) -> RuntimeError {
+    ErrorKind::IntegerOutOfRange { value, target }.into()
+}
+
+/// integers of other widths, converted when they fit.
+macro_rules! integer {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn expected_type() -> Type {
+                    Primitive::Integer.into()
+                }
+
+                fn from_value(value: Value) -> Result<Self, RuntimeError> {
+                    let int = i32::from_value(value)?;
+                    <$ty>::try_from(int).map_err(|_| out_of_range(int.into(), stringify!($ty)))
+                }
+            }
+
+            impl IntoValue for $ty {
+                fn produced_type() -> Type {
+                    Primitive::Integer.into()
+                }
+
+                fn into_value(self) -> Result<Value, RuntimeError> {
+                    let int = i32::try_from(self).map_err(|_| out_of_range(self as i128, "integer"))?;
+                    Ok(int.into())
+                }
+            }
+        )*
+    };
+}
+
+integer!(i8, i16, i64, isize, u8, u16, u32, u64, usize);
+
+/// integers are promoted to floats.
+impl FromValue for f32 {
+    fn expected_type() -> Type {
+        Primitive::Float.into()
+    }
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Integer(int) => Ok(int as f32),
+            Value::Float(flt) => Ok(flt),
+            _ => Err(mismatch(Self::expected_type(), &value)),
+        }
+    }
+}
+
+impl FromValue for f64 {
+    fn expected_type() -> Type {
+        Primitive::Float.into()
+    }
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        f32::from_value(value).map(f64::from)
+    }
+}
+
+impl FromValue for bool {
+    fn expected_type() -> Type {
+        Primitive::Bool.into()
+    }
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match &value {
+            &Value::Bool(bool) => Ok(bool),
+            _ => Err(mismatch(Self::expected_type(), &value)),
+        }
+    }
+}
+
+impl FromValue for String {
+    fn expected_type() -> Type {
+        Primitive::String.into()
+    }
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match &value {
+            Value::String(str) => Ok(str.clone()),
+            _ => Err(mismatch(Self::expected_type(), &value)),
+        }
+    }
+}
+
+impl FromValue for Gc<UserData> {
+    fn expected_type() -> Type {
+        Type::UserData
+    }
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match &value {
+            Value::UserData(user_data) => Ok(user_data.clone()),
+            _ => Err(mismatch(Self::expected_type(), &value)),
+        }
+    }
+}
+
+/// copies the items of a list.
+impl<T: FromValue> FromValue for Vec<T> {
+    fn expected_type() -> Type {
+        Type::List
+    }
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match &value {
+            Value::List(list) => {
+                let items = list.borrow().clone();
+                items.into_iter().map(T::from_value).collect()
+            }
+            _ => Err(mismatch(Self::expected_type(), &value)),
+        }
+    }
+}
+
+/// copies the fields of an object.
+impl<T: FromValue> FromValue for HashMap<String, T> {
+    fn expected_type() -> Type {
+        Type::Object
+    }
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match &value {
+            Value::Object(object) => {
+                let object = object.borrow();
+                let fields = object.fields().into_iter();
+                fields
+                    .map(|(name, value)| Ok((name.0.clone(), T::from_value(value.clone())?)))
+                    .collect()
+            }
+            _ => Err(mismatch(Self::expected_type(), &value)),
+        }
+    }
+}
+
+/// `none` is read as `None`.
+impl<T: FromValue> FromValue for Option<T> {
+    fn expected_type() -> Type {
+        Type::Any
+    }
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match &value {
+            Value::None => Ok(None),
+            _ => T::from_value(value).map(Some),
+        }
+    }
+}
+
+macro_rules! into_value {
+    ($($ty:ty => $primitive:ident),*) => {
+        $(
+            impl IntoValue for $ty {
+                fn produced_type() -> Type {
+                    Primitive::$primitive.into()
+                }
+
+                fn into_value(self) -> Result<Value, RuntimeError> {
+                    Ok(self.into())
+                }
+            }
+        )*
+    };
+}
+
+into_value!(i32 => Integer, f32 => Float, bool => Bool, String => String);
+
+impl IntoValue for f64 {
+    fn produced_type() -> Type {
+        Primitive::Float.into()
+    }
+
+    fn into_value(self) -> Result<Value, RuntimeError> {
+        Ok((self as f32).into())
+    }
+}
+
+impl IntoValue for &str {
+    fn produced_type() -> Type {
+        Primitive::String.into()
+    }
+
+    fn into_value(self) -> Result<Value, RuntimeError> {
+        Ok(self.to_string().into())
+    }
+}
+
+impl IntoValue for () {
+    fn produced_type() -> Type {
+        Type::None
+    }
+
+    fn into_value(self) -> Result<Value, RuntimeError> {
+        Ok(Value::None)
+    }
+}
+
+impl IntoValue for UserData {
+    fn produced_type() -> Type {
+        Type::UserData
+    }
+
+    fn into_value(self) -> Result<Value, RuntimeError> {
+        Ok(self.into())
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn produced_type() -> Type {
+        Type::List
+    }
+
+    fn into_value(self) -> Result<Value, RuntimeError> {
+        let items: Result<Vec<_>, _> = self.into_iter().map(T::into_value).collect();
+        Ok(items?.into())
+    }
+}
+
+impl<T: IntoValue> IntoValue for HashMap<String, T> {
+    fn produced_type() -> Type {
+        Type::Object
+    }
+
+    fn into_value(self) -> Result<Value, RuntimeError> {
+        let fields: Result<HashMap<Name, Value>, RuntimeError> = self
+            .into_iter()
+            .map(|(name, value)| Ok((name.into(), value.into_value()?)))
+            .collect();
+        Ok(fields?.into())
+    }
+}
+
+/// `None` gives `none`.
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn produced_type() -> Type {
+        Type::Any
+    }
+
+    fn into_value(self) -> Result<Value, RuntimeError> {
+        match self {
+            Some(value) => value.into_value(),
+            None => Ok(Value::None),
+        }
+    }
+
+    fn allocates() -> bool {
+        T::allocates()
+    }
+}
+
+/// `Err` raises the error from the native.
+impl<T: IntoValue, E: Into<RuntimeError>> IntoValue for Result<T, E> {
+    fn produced_type() -> Type {
+        T::produced_type()
+    }
+
+    fn into_value(self) -> Result<Value, RuntimeError> {
+        self.map_err(Into::into)?.into_value()
+    }
+
+    fn allocates() -> bool {
+        T::allocates()
+    }
+}
+
+/// rust values which can be passed as the arguments of a call, as a tuple
+/// of values each converted with `IntoValue`.
+pub trait IntoArgs {
+    fn into_args(self) -> Result<Vec<Value>, RuntimeError>;
+}
+
+impl IntoArgs for Vec<Value> {
+    fn into_args(self) -> Result<Vec<Value>, RuntimeError> {
+        Ok(self)
+    }
+}
+
+macro_rules! into_args {
+    ($($arg:ident),*) => {
+        impl<$($arg: IntoValue),*> IntoArgs for ($($arg,)*) {
+            #[allow(non_snake_case)]
+            fn into_args(self) -> Result<Vec<Value>, RuntimeError> {
+                let ($($arg,)*) = self;
+                Ok(vec![$($arg.into_value()?),*])
+            }
+        }
+    };
+}
+
+into_args!();
+into_args!(A);
+into_args!(A, B);
+into_args!(A, B, C);
+into_args!(A, B, C, D);
+into_args!(A, B, C, D, E);
+into_args!(A, B, C, D, E, G);
+
+/// rust function which can be registered as a native, its arguments and
+/// result being converted and typed from its signature.
+pub trait TypedNative<Args> {
+    fn into_function(self, name: Name) -> Function;
+}
+
+macro_rules! typed_native {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg),*> TypedNative<($($arg,)*)> for F
+        where
+            F: FnMut($($arg),*) -> R + 'static,
+            R: IntoValue,
+            $($arg: FromValue),*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn into_function(self, name: Name) -> Function {
+                let parameters = vec![$($arg::expected_type()),*];
+                let signature = Signature::new(parameters, R::produced_type());
+                let arity = Arity::exact(signature.parameters.len());
+                let caller = RefCell::new(self);
+                let mut function =
+                    Function::new_contextual_variadic_native(name, arity, move |context, args| {
+                        let mut args = args.into_iter();
+                        $(let $arg = $arg::from_value(args.next().unwrap_or(Value::None))?;)*
+                        let result = (caller.borrow_mut())($($arg),*).into_value()?;
+                        if R::allocates() {
+                            context.allocate(&result)?;
+                        }
+                        Ok(result)
+                    });
+                function.set_signature(signature);
+                function
+            }
+        }
+    };
+}
+
+typed_native!();
+typed_native!(A);
+typed_native!(A, B);
+typed_native!(A, B, C);
+typed_native!(A, B, C, D);
+typed_native!(A, B, C, D, E);
+typed_native!(A, B, C, D, E, G);
@@ -0,0 +1,274 @@
+use std::fmt::Display;
+
+use lorgn_lang::{
+    ast::{BinaryOperator, Name, NodeId, Path, UnaryOperator},
+    typing::{Arity, Type},
+};
+
+/// function being run, along with the node it was evaluating.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// module of the function, `None` for closures created by the host.
+    pub module: Option<Name>,
+    pub function: Name,
+    pub node: Option<NodeId>,
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Name(function) = &self.function;
+        match &self.module {
+            Some(Name(module)) => write!(f, "at {module}::{function}")?,
+            None => write!(f, "at {function}")?,
+        }
+        match self.node {
+            Some(NodeId(id)) => write!(f, " (node #{id})"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// functions active when an error was raised, the innermost first.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StackTrace(pub Vec<Frame>);
+
+impl StackTrace {
+    pub fn frames(&self) -> &[Frame] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Display for StackTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, frame) in self.0.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "  {frame}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    /// innermost node being evaluated when the error was raised.
+    pub node: Option<NodeId>,
+    /// functions active when the error was raised.
+    pub trace: StackTrace,
+}
+
+impl RuntimeError {
+    pub fn at(mut self, node: Option<NodeId>) -> Self {
+        self.node = self.node.or(node);
+        self
+    }
+}
+
+impl From<ErrorKind> for RuntimeError {
+    fn from(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            node: None,
+            trace: StackTrace::default(),
+        }
+    }
+}
+
+/// error raised by a native with a message.
+impl From<String> for RuntimeError {
+    fn from(message: String) -> Self {
+        ErrorKind::Native(message).into()
+    }
+}
+
+impl From<&str> for RuntimeError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.node {
+            Some(NodeId(id)) => write!(f, "{} (node #{id})", self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    UndefinedVariable(Name),
+    UnknownModule(Name),
+    UnknownFunction(Path),
+    PrivateFunction(Path),
+    UnknownItem(Path),
+    PrivateItem(Path),
+    ConstAssignment(Name),
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    ArityMismatch {
+        function: Name,
+        expected: Arity,
+        found: usize,
+    },
+    ParameterMismatch {
+        function: Name,
+        parameter: Name,
+        expected: Type,
+        found: Type,
+    },
+    ResultMismatch {
+        function: Name,
+        expected: Type,
+        found: Type,
+    },
+    IndexOutOfRange {
+        index: i32,
+        length: usize,
+    },
+    /// integer not fitting in the type it is converted to.
+    IntegerOutOfRange {
+        value: i128,
+        target: &'static str,
+    },
+    MissingField(Name),
+    UnknownMethod {
+        type_name: &'static str,
+        method: Name,
+    },
+    InvalidOperands {
+        operator: BinaryOperator,
+        left: Type,
+        right: Type,
+    },
+    InvalidOperand {
+        operator: UnaryOperator,
+        operand: Type,
+    },
+    DivisionByZero,
+    BreakOutsideLoop,
+    ImportCycle(Path),
+    /// function or import defined several times in a module.
+    DuplicateItem(Path),
+    /// error raised by a native function.
+    Native(String),
+    OutOfFuel,
+    Cancelled,
+    CallDepthExceeded(usize),
+    ScopeLimitExceeded(usize),
+    HeapLimitExceeded(usize),
+    NestingTooDeep(usize),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedVariable(Name(name)) => write!(f, "undefined variable '{name}'"),
+            Self::UnknownModule(Name(module)) => write!(f, "unknown module '{module}'"),
+            Self::UnknownFunction(Path {
+                module: Name(module),
+                item: Name(item),
+            }) => write!(f, "unknown function '{module}::{item}'"),
+            Self::PrivateFunction(Path {
+                module: Name(module),
+                item: Name(item),
+            }) => write!(f, "function '{module}::{item}' is not exported"),
+            Self::UnknownItem(Path {
+                module: Name(module),
+                item: Name(item),
+            }) => write!(f, "unknown constant or global '{module}::{item}'"),
+            Self::PrivateItem(Path {
+                module: Name(module),
+                item: Name(item),
+            }) => write!(f, "constant or global '{module}::{item}' is not exported"),
+            Self::ConstAssignment(Name(name)) => write!(f, "cannot assign constant '{name}'"),
+            Self::TypeMismatch { expected, found } => {
+                write!(f, "type mismatch, expected {expected} but found {found}")
+            }
+            Self::ArityMismatch {
+                function: Name(function),
+                expected,
+                found,
+            } => write!(
+                f,
+                "function '{function}' expects {expected} arguments but received {found}"
+            ),
+            Self::ParameterMismatch {
+                function: Name(function),
+                parameter: Name(parameter),
+                expected,
+                found,
+            } => write!(
+                f,
+                "parameter '{parameter}' of function '{function}' expects {expected} but received {found}"
+            ),
+            Self::ResultMismatch {
+                function: Name(function),
+                expected,
+                found,
+            } => write!(
+                f,
+                "function '{function}' should return {expected} but returned {found}"
+            ),
+            Self::IndexOutOfRange { index, length } => {
+                write!(f, "index {index} is out of range for a list of length {length}")
+            }
+            Self::IntegerOutOfRange { value, target } => {
+                write!(f, "integer {value} does not fit in {target}")
+            }
+            Self::MissingField(Name(name)) => write!(f, "missing field '{name}'"),
+            Self::UnknownMethod {
+                type_name,
+                method: Name(method),
+            } => write!(f, "type '{type_name}' has no method '{method}'"),
+            Self::InvalidOperands {
+                operator,
+                left,
+                right,
+            } => write!(
+                f,
+                "cannot apply '{}' to {left} and {right}",
+                operator.symbol()
+            ),
+            Self::InvalidOperand { operator, operand } => {
+                write!(f, "cannot apply '{}' to {operand}", operator.symbol())
+            }
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::BreakOutsideLoop => write!(f, "break outside of loop"),
+            Self::ImportCycle(Path {
+                module: Name(module),
+                item: Name(item),
+            }) => write!(f, "import of '{module}::{item}' is cyclic"),
+            Self::DuplicateItem(Path {
+                module: Name(module),
+                item: Name(item),
+            }) => write!(f, "'{module}::{item}' is defined more than once"),
+            Self::Native(message) => f.write_str(message),
+            Self::OutOfFuel => write!(f, "evaluation ran out of fuel"),
+            Self::Cancelled => write!(f, "evaluation was cancelled"),
+            Self::CallDepthExceeded(max) => {
+                write!(f, "call depth exceeded the limit of {max}")
+            }
+            Self::ScopeLimitExceeded(max) => {
+                write!(f, "number of scopes exceeded the limit of {max}")
+            }
+            Self::HeapLimitExceeded(max) => {
+                write!(f, "allocations exceeded the limit of {max} bytes")
+            }
+            Self::NestingTooDeep(max) => {
+                write!(f, "expressions nested deeper than the limit of {max}")
+            }
+        }
+    }
+}
@@ -1,25 +1,60 @@
-use std::fmt::Debug;
+use std::{cell::RefCell, fmt::Debug, rc::Rc};
 
-use lorgn_lang::ast::{FnDef, Name, Path};
+use lorgn_lang::{
+    ast::{FnDef, Name, Path},
+    typing::{Arity, Signature},
+};
 
-use crate::{runtime::Context, Value};
+use crate::{
+    runtime::{Compiled, Context},
+    ErrorKind, FromValue, IntoArgs, Runtime, RuntimeError, Value,
+};
 
 #[derive(Debug)]
 pub struct Imported {
-    _path: Path,
+    path: Path,
+}
+
+type ContextualHandler = Box<dyn Fn(&mut Context, Vec<Value>) -> Result<Value, RuntimeError>>;
+
+pub enum NativeHandler {
+    Simple(RefCell<Box<dyn FnMut(Vec<Value>) -> Value>>),
+    /// handler given the calling context, which may call back into scripts
+    /// and so must be reentrant.
+    Contextual(ContextualHandler),
 }
 
 pub struct Native {
-    arg_count: usize,
-    handler: Box<dyn FnMut(Vec<Value>) -> Value>,
+    arity: Arity,
+    handler: NativeHandler,
 }
 
 impl Native {
-    pub fn run(&mut self, args: Vec<Value>) -> Value {
-        if self.arg_count != args.len() {
-            panic!("too few args")
-        }
-        (self.handler)(args)
+    pub fn run(
+        &self,
+        name: &Name,
+        args: Vec<Value>,
+        context: &mut Context,
+    ) -> Result<Value, RuntimeError> {
+        // every native shows in the stack trace, even failing on its
+        // arguments.
+        context.in_frame(name, |context| {
+            if !self.arity.accepts(args.len()) {
+                return Err(ErrorKind::ArityMismatch {
+                    function: name.clone(),
+                    expected: self.arity,
+                    found: args.len(),
+                }
+                .into());
+            }
+            match &self.handler {
+                NativeHandler::Simple(handler) => {
+                    let mut handler = handler.borrow_mut();
+                    Ok((handler)(args))
+                }
+                NativeHandler::Contextual(handler) => (handler)(context, args),
+            }
+        })
     }
 }
 
@@ -32,7 +67,8 @@ impl Debug for Native {
 #[derive(Debug)]
 pub enum FnImpl {
     Imported(Imported),
-    Defined(FnDef),
+    /// script function, along with its code once compiled.
+    Defined(Rc<FnDef>, Compiled),
     Native(Native),
 }
 
@@ -40,52 +76,179 @@ pub enum FnImpl {
 pub struct Function {
     name: Name,
     implem: FnImpl,
+    signature: Option<Signature>,
 }
 
 impl Function {
     pub fn new_defined(name: Name, definition: FnDef) -> Self {
-        let implem = FnImpl::Defined(definition);
-        Self { name, implem }
+        let implem = FnImpl::Defined(Rc::new(definition), Compiled::default());
+        let signature = None;
+        Self {
+            name,
+            implem,
+            signature,
+        }
     }
 
     pub fn new_imported(name: Name, module: Name) -> Self {
         let implem = FnImpl::Imported(Imported {
-            _path: Path {
+            path: Path {
                 item: name.clone(),
                 module,
             },
         });
-        Self { name, implem }
+        let signature = None;
+        Self {
+            name,
+            implem,
+            signature,
+        }
     }
     pub fn new_native<const N: usize>(
         name: Name,
         mut caller: impl FnMut([Value; N]) -> Value + 'static,
     ) -> Self {
-        let handler = Box::new(move |values: Vec<Value>| {
-            let casted = values.try_into().unwrap();
-            (caller)(casted)
-        }) as Box<dyn FnMut(Vec<Value>) -> Value>;
+        // the arity is checked before calling, so the conversion cannot fail.
+        let handler = move |values: Vec<Value>| (caller)(values.try_into().unwrap());
+        Self::new_variadic_native(name, Arity::exact(N), handler)
+    }
+
+    /// native receiving the arguments as given, their count being within
+    /// `arity`.
+    pub fn new_variadic_native(
+        name: Name,
+        arity: Arity,
+        caller: impl FnMut(Vec<Value>) -> Value + 'static,
+    ) -> Self {
+        let handler = Box::new(caller) as Box<dyn FnMut(Vec<Value>) -> Value>;
         Self {
             name,
             implem: FnImpl::Native(Native {
-                arg_count: N,
-                handler,
+                arity,
+                handler: NativeHandler::Simple(RefCell::new(handler)),
             }),
+            signature: None,
         }
     }
 
-    pub fn call(&mut self, args: Vec<Value>, context: &mut Context) -> Value {
-        match &mut self.implem {
-            FnImpl::Defined(definition) => context.run_fun(definition, args),
-            FnImpl::Native(native) => native.run(args),
-            FnImpl::Imported(_imported) => {
-                todo!() // let mut res = context.find_function(&imported.path).unwrap();
-                        // res.call(args, context)
-            }
+    pub fn new_contextual_native<const N: usize>(
+        name: Name,
+        caller: impl Fn(&mut Context, [Value; N]) -> Result<Value, RuntimeError> + 'static,
+    ) -> Self {
+        let handler = move |context: &mut Context, values: Vec<Value>| {
+            (caller)(context, values.try_into().unwrap())
+        };
+        Self::new_contextual_variadic_native(name, Arity::exact(N), handler)
+    }
+
+    pub fn new_contextual_variadic_native(
+        name: Name,
+        arity: Arity,
+        caller: impl Fn(&mut Context, Vec<Value>) -> Result<Value, RuntimeError> + 'static,
+    ) -> Self {
+        let handler = Box::new(caller) as ContextualHandler;
+        Self {
+            name,
+            implem: FnImpl::Native(Native {
+                arity,
+                handler: NativeHandler::Contextual(handler),
+            }),
+            signature: None,
+        }
+    }
+
+    pub fn call(&self, args: Vec<Value>, context: &mut Context) -> Result<Value, RuntimeError> {
+        match &self.implem {
+            FnImpl::Defined(definition, code) => context.run_fun(definition, code, args),
+            FnImpl::Native(native) => native.run(&self.name, args, context),
+            FnImpl::Imported(imported) => context.call_function(&imported.path, args),
         }
     }
 
     pub fn name(&self) -> &Name {
         &self.name
     }
+
+    pub fn set_signature(&mut self, signature: Signature) {
+        self.signature = Some(signature);
+    }
+
+    /// declared signature, or an untyped one matching the function arity,
+    /// `None` for imports which take the signature of their target.
+    pub fn signature(&self) -> Option<Signature> {
+        if let Some(signature) = &self.signature {
+            return Some(signature.clone());
+        }
+        match &self.implem {
+            FnImpl::Defined(definition, _) => Some(definition.signature()),
+            FnImpl::Native(native) => Some(Signature::untyped(native.arity)),
+            FnImpl::Imported(_) => None,
+        }
+    }
+
+    /// definition of a function written in a script.
+    pub(crate) fn definition(&self) -> Option<&FnDef> {
+        match &self.implem {
+            FnImpl::Defined(definition, _) => Some(definition),
+            _ => None,
+        }
+    }
+
+    pub fn imported_path(&self) -> Option<&Path> {
+        match &self.implem {
+            FnImpl::Imported(imported) => Some(&imported.path),
+            _ => None,
+        }
+    }
+}
+
+/// function resolved once from its path, to be called from the host without
+/// looking it up again.
+///
+/// the handle keeps calling the function it was resolved to, even after its
+/// module is registered again.
+#[derive(Debug, Clone)]
+pub struct FunctionHandle {
+    /// module the function is defined in, once imports are followed.
+    module: Name,
+    function: Rc<Function>,
+}
+
+impl FunctionHandle {
+    pub(crate) fn new(module: Name, function: Rc<Function>) -> Self {
+        Self { module, function }
+    }
+
+    pub fn module(&self) -> &Name {
+        &self.module
+    }
+
+    pub fn name(&self) -> &Name {
+        self.function.name()
+    }
+
+    pub fn signature(&self) -> Option<Signature> {
+        self.function.signature()
+    }
+
+    pub fn call(&self, runtime: &mut Runtime, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        self.call_with(&mut runtime.context(), args)
+    }
+
+    pub(crate) fn call_with(
+        &self,
+        context: &mut Context,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        context.call_in(&self.module, &self.function, args)
+    }
+
+    /// calls the function with converted arguments, converting its result.
+    pub fn call_typed<R: FromValue>(
+        &self,
+        runtime: &mut Runtime,
+        args: impl IntoArgs,
+    ) -> Result<R, RuntimeError> {
+        R::from_value(self.call(runtime, args.into_args()?)?)
+    }
 }
@@ -2,13 +2,24 @@ mod module;
 pub use module::Module;
 
 mod function;
-pub use function::Function;
+pub use function::{Function, FunctionHandle};
 
 mod runtime;
-pub use runtime::Runtime;
+pub use runtime::{CancelToken, Context, Engine, Limits, Runtime};
 
 mod value;
-pub use value::Value;
+pub use value::{Captures, Closure, Value};
+
+mod error;
+pub use error::{ErrorKind, Frame, RuntimeError, StackTrace};
+
+mod convert;
+pub use convert::{FromValue, IntoArgs, IntoValue, TypedNative};
+
+mod userdata;
+pub use userdata::{UserData, UserType};
+
+pub mod stdlib;
 
 #[test]
 fn test_runtime() {
@@ -20,12 +31,1653 @@ fn test_runtime() {
         println!("{item:?}");
         Value::None
     });
-    runtime.register(module);
-    runtime.evaluate(Expr::FnCall(FnCall {
-        fn_path: Path {
-            module: "std".into(),
-            item: "print".into(),
+    runtime.register(module).unwrap();
+    runtime
+        .evaluate(Expr::FnCall(FnCall {
+            fn_path: Path {
+                module: "std".into(),
+                item: "print".into(),
+            },
+            arguments: vec![Expr::Litteral("hello yorld".into()).boxed()],
+        }))
+        .unwrap();
+}
+
+#[test]
+fn test_runtime_errors() {
+    use lorgn_lang::ast::{Break, Condition, Expr, FnCall, Invoke, Path};
+
+    let mut runtime = Runtime::default();
+    let mut module = Module::new_empty("std");
+    module.push_native("id".into(), |[item]: [Value; 1]| item);
+    runtime.register(module).unwrap();
+
+    let call = |module: &str, item: &str, arguments| {
+        Expr::FnCall(FnCall {
+            fn_path: Path {
+                module: module.into(),
+                item: item.into(),
+            },
+            arguments,
+        })
+    };
+
+    let undefined = Expr::Invoke(Invoke {
+        variable_name: "x".into(),
+    });
+    assert_eq!(
+        runtime.evaluate(undefined).map_err(|e| e.kind),
+        Err(ErrorKind::UndefinedVariable("x".into()))
+    );
+
+    assert_eq!(
+        runtime
+            .evaluate(call("nope", "id", vec![]))
+            .map_err(|e| e.kind),
+        Err(ErrorKind::UnknownModule("nope".into()))
+    );
+
+    let path = Path {
+        module: "std".into(),
+        item: "nope".into(),
+    };
+    assert_eq!(
+        runtime
+            .evaluate(call("std", "nope", vec![]))
+            .map_err(|e| e.kind),
+        Err(ErrorKind::UnknownFunction(path))
+    );
+
+    assert_eq!(
+        runtime
+            .evaluate(call("std", "id", vec![]))
+            .map_err(|e| e.kind),
+        Err(ErrorKind::ArityMismatch {
+            function: "id".into(),
+            expected: 1.into(),
+            found: 0
+        })
+    );
+
+    let condition = Expr::Condition(Condition {
+        condition: Expr::Litteral(1.into()).boxed(),
+        true_case: Expr::Litteral(1.into()).boxed(),
+        false_case: Expr::Litteral(2.into()).boxed(),
+    });
+    assert_eq!(
+        runtime.evaluate(condition).map_err(|e| e.kind),
+        Err(ErrorKind::TypeMismatch {
+            expected: "bool",
+            found: "integer"
+        })
+    );
+
+    let break_ = Expr::Break(Break {
+        expression: Expr::Litteral(1.into()).boxed(),
+    });
+    assert_eq!(
+        runtime.evaluate(break_).map_err(|e| e.kind),
+        Err(ErrorKind::BreakOutsideLoop)
+    );
+}
+
+#[test]
+fn test_recursion() {
+    use lorgn_lang::ast::{
+        self, Block, Condition, Export, Expr, FnCall, FnDef, Invoke, Path, TopLevel,
+    };
+
+    let call = |module: &str, item: &str, arguments| {
+        Expr::FnCall(FnCall {
+            fn_path: Path {
+                module: module.into(),
+                item: item.into(),
+            },
+            arguments,
+        })
+        .boxed()
+    };
+    let var = |name: &str| {
+        Expr::Invoke(Invoke {
+            variable_name: name.into(),
+        })
+        .boxed()
+    };
+    let int = |value: i32| Expr::Litteral(value.into()).boxed();
+    let fndef = |name: &str, body| {
+        TopLevel::FnDef(FnDef {
+            meta: Default::default(),
+            name: name.into(),
+            parameters: vec!["n".into()],
+            result: None,
+            expressions: Block {
+                expressions: vec![body],
+            },
+        })
+    };
+    let if_zero = |then, otherwise| {
+        Expr::Condition(Condition {
+            condition: call("math", "eq", vec![var("n"), int(0)]),
+            true_case: then,
+            false_case: otherwise,
+        })
+        .boxed()
+    };
+    let pred = || call("math", "sub", vec![var("n"), int(1)]);
+
+    let mut math = Module::new_empty("math");
+    math.push_native("eq".into(), |[a, b]: [Value; 2]| (a == b).into());
+    math.push_native("sub".into(), |[a, b]: [Value; 2]| {
+        (a.into_i32().unwrap() - b.into_i32().unwrap()).into()
+    });
+    math.push_native("mul".into(), |[a, b]: [Value; 2]| {
+        (a.into_i32().unwrap() * b.into_i32().unwrap()).into()
+    });
+
+    let program = ast::Module {
+        items: vec![
+            TopLevel::Export(Export {
+                items: vec!["fact".into(), "is_even".into()],
+            }),
+            fndef(
+                "fact",
+                if_zero(
+                    int(1),
+                    call(
+                        "math",
+                        "mul",
+                        vec![var("n"), call("main", "fact", vec![pred()])],
+                    ),
+                ),
+            ),
+            fndef(
+                "is_even",
+                if_zero(
+                    Expr::Litteral(true.into()).boxed(),
+                    call("main", "is_odd", vec![pred()]),
+                ),
+            ),
+            fndef(
+                "is_odd",
+                if_zero(
+                    Expr::Litteral(false.into()).boxed(),
+                    call("main", "is_even", vec![pred()]),
+                ),
+            ),
+        ],
+    };
+
+    let mut runtime = Runtime::default();
+    runtime.register(math).unwrap();
+    runtime.register(Module::from_ast("main", program)).unwrap();
+
+    let fact = runtime.evaluate(call("main", "fact", vec![int(5)]).expr);
+    assert_eq!(fact, Ok(Value::Integer(120)));
+    let is_even = runtime.evaluate(call("main", "is_even", vec![int(7)]).expr);
+    assert_eq!(is_even, Ok(Value::Bool(false)));
+    let path = Path {
+        module: "main".into(),
+        item: "is_odd".into(),
+    };
+    let is_odd = runtime.evaluate(call("main", "is_odd", vec![int(7)]).expr);
+    assert_eq!(
+        is_odd.map_err(|e| e.kind),
+        Err(ErrorKind::PrivateFunction(path))
+    );
+}
+
+#[test]
+fn test_imports() {
+    use lorgn_lang::ast::{self, Block, Export, Expr, FnCall, FnDef, Import, Path, TopLevel};
+
+    let call = |module: &str, item: &str| {
+        Expr::FnCall(FnCall {
+            fn_path: Path {
+                module: module.into(),
+                item: item.into(),
+            },
+            arguments: vec![],
+        })
+    };
+    let fndef = |name: &str, body: Expr| {
+        TopLevel::FnDef(FnDef {
+            meta: Default::default(),
+            name: name.into(),
+            parameters: vec![],
+            result: None,
+            expressions: Block {
+                expressions: vec![body.boxed()],
+            },
+        })
+    };
+    let import = |module: &str, item: &str| {
+        TopLevel::Import(Import {
+            module_name: module.into(),
+            items: vec![item.into()],
+        })
+    };
+    let export = |items: &[&str]| {
+        TopLevel::Export(Export {
+            items: items.iter().map(|&item| item.into()).collect(),
+        })
+    };
+    let module = |name: &str, items| Module::from_ast(name, ast::Module { items });
+
+    let mut runtime = Runtime::default();
+    let lib = module(
+        "lib",
+        vec![
+            export(&["answer"]),
+            fndef("answer", Expr::Litteral(42.into())),
+            fndef("secret", Expr::Litteral(0.into())),
+        ],
+    );
+    let public: Vec<_> = lib.public_functions().map(|f| f.name().clone()).collect();
+    assert_eq!(public, vec!["answer".into()]);
+    runtime.register(lib).unwrap();
+    let main = module(
+        "main",
+        vec![
+            export(&["run", "answer"]),
+            import("lib", "answer"),
+            fndef("run", call("main", "answer")),
+        ],
+    );
+    runtime.register(main).unwrap();
+    assert_eq!(
+        runtime.evaluate(call("main", "run")),
+        Ok(Value::Integer(42))
+    );
+
+    let missing_module = module("bad", vec![import("nope", "answer")]);
+    assert_eq!(
+        runtime.register(missing_module).map_err(|e| e.kind),
+        Err(ErrorKind::UnknownModule("nope".into()))
+    );
+
+    let private_item = module("bad", vec![import("lib", "secret")]);
+    let path = Path {
+        module: "lib".into(),
+        item: "secret".into(),
+    };
+    assert_eq!(
+        runtime.register(private_item).map_err(|e| e.kind),
+        Err(ErrorKind::PrivateFunction(path))
+    );
+
+    let missing_item = module("bad", vec![import("lib", "nope")]);
+    let path = Path {
+        module: "lib".into(),
+        item: "nope".into(),
+    };
+    assert_eq!(
+        runtime.register(missing_item).map_err(|e| e.kind),
+        Err(ErrorKind::UnknownFunction(path))
+    );
+
+    let cyclic = module("lib", vec![export(&["answer"]), import("main", "answer")]);
+    let path = Path {
+        module: "main".into(),
+        item: "answer".into(),
+    };
+    assert_eq!(
+        runtime.register(cyclic).map_err(|e| e.kind),
+        Err(ErrorKind::ImportCycle(path))
+    );
+
+    let duplicate = module(
+        "bad",
+        vec![
+            import("lib", "answer"),
+            fndef("answer", Expr::Litteral(0.into())),
+        ],
+    );
+    let path = Path {
+        module: "bad".into(),
+        item: "answer".into(),
+    };
+    assert_eq!(
+        runtime.register(duplicate).map_err(|e| e.kind),
+        Err(ErrorKind::DuplicateItem(path.clone()))
+    );
+    let twice = module(
+        "bad",
+        vec![
+            fndef("answer", Expr::Litteral(0.into())),
+            fndef("answer", Expr::Litteral(1.into())),
+        ],
+    );
+    assert_eq!(
+        runtime.register(twice).map_err(|e| e.kind),
+        Err(ErrorKind::DuplicateItem(path))
+    );
+}
+
+#[test]
+fn test_error_node() {
+    use lorgn_lang::{
+        ast::{Expr, FnCall, NodeId, Path},
+        parser::parse_module,
+    };
+
+    let source = "export { main }; fn main() { x = 1; y }";
+    let program = parse_module(source).unwrap();
+    let mut runtime = Runtime::default();
+    runtime.register(Module::from_ast("main", program)).unwrap();
+    let error = runtime
+        .evaluate(Expr::FnCall(FnCall {
+            fn_path: Path {
+                module: "main".into(),
+                item: "main".into(),
+            },
+            arguments: vec![],
+        }))
+        .unwrap_err();
+    assert_eq!(error.kind, ErrorKind::UndefinedVariable("y".into()));
+    assert_eq!(error.node, Some(NodeId(4)));
+    assert_eq!(error.to_string(), "undefined variable 'y' (node #4)");
+}
+
+#[test]
+fn test_type_check() {
+    use lorgn_lang::{
+        parser::parse_module,
+        typing::{Primitive, Signature, Type, TypeErrorKind},
+    };
+
+    let integer: Type = Primitive::Integer.into();
+    let mut runtime = Runtime::default();
+    let mut math = Module::new_empty("math");
+    math.push_native("neg".into(), |[n]: [Value; 1]| {
+        (-n.into_i32().unwrap()).into()
+    })
+    .set_signature(Signature::new(vec![integer.clone()], integer.clone()));
+    runtime.register(math).unwrap();
+
+    let valid = parse_module("fn main() { math::neg(1) }").unwrap();
+    assert_eq!(runtime.check("main", &valid), Ok(()));
+
+    let invalid = parse_module("fn main() { math::neg(\"a\"); math::neg() }").unwrap();
+    let errors = runtime.check("main", &invalid).unwrap_err();
+    let kinds: Vec<_> = errors.into_iter().map(|e| e.kind).collect();
+    let path = lorgn_lang::ast::Path {
+        module: "math".into(),
+        item: "neg".into(),
+    };
+    assert_eq!(
+        kinds,
+        vec![
+            TypeErrorKind::Mismatch {
+                expected: integer,
+                found: Primitive::String.into()
+            },
+            TypeErrorKind::ArityMismatch {
+                function: path,
+                expected: 1.into(),
+                found: 0
+            }
+        ]
+    );
+}
+
+#[test]
+fn test_annotations() {
+    use lorgn_lang::{
+        ast::{Expr, FnCall, Litteral, Path},
+        parser::parse_module,
+        typing::Primitive,
+    };
+
+    let mut runtime = Runtime::default();
+    let source = r#"
+        export { id, wrong, half };
+        fn id(n: integer) -> integer { n }
+        fn wrong(n) -> string { n }
+        fn half(x: float) -> float { x / 2 }
+    "#;
+    runtime
+        .register(Module::from_ast("main", parse_module(source).unwrap()))
+        .unwrap();
+    let mut call = |item: &str, arg: Litteral| {
+        let call = Expr::FnCall(FnCall {
+            fn_path: Path {
+                module: "main".into(),
+                item: item.into(),
+            },
+            arguments: vec![Expr::Litteral(arg).boxed()],
+        });
+        runtime.evaluate(call).map_err(|e| e.kind)
+    };
+
+    assert_eq!(call("id", 3.into()), Ok(3.into()));
+    assert_eq!(
+        call("id", "a".into()),
+        Err(ErrorKind::ParameterMismatch {
+            function: "id".into(),
+            parameter: "n".into(),
+            expected: Primitive::Integer.into(),
+            found: Primitive::String.into(),
+        })
+    );
+    assert_eq!(call("half", 3.into()), Ok(1.5.into()));
+    assert_eq!(call("wrong", "a".into()), Ok(Value::from("a".to_string())));
+    assert_eq!(
+        call("wrong", 1.into()),
+        Err(ErrorKind::ResultMismatch {
+            function: "wrong".into(),
+            expected: Primitive::String.into(),
+            found: Primitive::Integer.into(),
+        })
+    );
+}
+
+#[test]
+fn test_closures() {
+    use lorgn_lang::{
+        ast::{Expr, FnCall, Path},
+        parser::parse_module,
+    };
+
+    let source = r#"
+        export { main, apply };
+        fn main() {
+            offset = 10;
+            add = fn(x: integer) { main::sum(x, offset) };
+            offset = 0;
+            counter = fn(f) { fn(x) { f(f(x)) } };
+            counter(add)(1)
+        }
+        fn apply(f, x) { f(x) }
+        fn sum(a, b) { lib::sum(a, b) }
+    "#;
+    let mut lib = Module::new_empty("lib");
+    lib.push_native("sum".into(), |[a, b]: [Value; 2]| {
+        (a.into_i32().unwrap() + b.into_i32().unwrap()).into()
+    });
+    let mut runtime = Runtime::default();
+    runtime.register(lib).unwrap();
+    runtime
+        .register(Module::from_ast("main", parse_module(source).unwrap()))
+        .unwrap();
+    let call = |item: &str| {
+        Expr::FnCall(FnCall {
+            fn_path: Path {
+                module: "main".into(),
+                item: item.into(),
+            },
+            arguments: vec![],
+        })
+    };
+    assert_eq!(runtime.evaluate(call("main")), Ok(21.into()));
+
+    let mut apply = call("apply");
+    if let Expr::FnCall(fn_call) = &mut apply {
+        fn_call.arguments = vec![
+            Expr::Litteral(1.into()).boxed(),
+            Expr::Litteral(2.into()).boxed(),
+        ];
+    }
+    assert_eq!(
+        runtime.evaluate(apply).map_err(|e| e.kind),
+        Err(ErrorKind::TypeMismatch {
+            expected: "function",
+            found: "integer"
+        })
+    );
+}
+
+#[test]
+fn test_closure_captures() {
+    use lorgn_lang::parser::parse_expr;
+
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let mut runtime = Runtime::with_std();
+        runtime.set_engine(engine);
+        let mut eval = |source: &str| {
+            let expr = parse_expr(source).unwrap().expr;
+            runtime.evaluate(expr).unwrap()
+        };
+        let list = |items: &[i32]| Value::from(items.iter().map(|&i| i.into()).collect::<Vec<_>>());
+
+        // variables are copied, assignments on either side are not shared.
+        assert_eq!(
+            eval("{ x = 1; g = fn() { x = x + 1; x }; [g(), g(), x] }"),
+            list(&[2, 2, 1])
+        );
+        assert_eq!(eval("{ x = 1; g = fn() { x }; x = 5; g() }"), 1.into());
+        // lists and objects are shared by reference.
+        assert_eq!(
+            eval("{ l = [0]; g = fn() { l[0] = l[0] + 1 }; g(); g(); l }"),
+            list(&[2])
+        );
+        assert_eq!(
+            eval("{ o = #{ n: 0 }; g = fn() { o.n = o.n + 1 }; g(); o.n }"),
+            1.into()
+        );
+    }
+}
+
+#[test]
+fn test_mutations() {
+    use lorgn_lang::{
+        ast::{Expr, FnCall, Path},
+        parser::parse_module,
+    };
+
+    let source = r#"
+        export { main, out_of_range, missing };
+        fn main() {
+            list = [1, [2]];
+            alias = list;
+            alias[1][0] = 3;
+            list[0] = 4;
+            object = #{ count: 1 };
+            same = object;
+            same.count = object.count;
+            object["count"] = 2;
+            [alias, same.count]
+        }
+        fn out_of_range() { [1, 2][2] }
+        fn missing() { #{ x: 1 }.y }
+    "#;
+    let mut runtime = Runtime::default();
+    runtime
+        .register(Module::from_ast("main", parse_module(source).unwrap()))
+        .unwrap();
+    let mut call = |item: &str| {
+        let call = Expr::FnCall(FnCall {
+            fn_path: Path {
+                module: "main".into(),
+                item: item.into(),
+            },
+            arguments: vec![],
+        });
+        runtime.evaluate(call).map_err(|e| e.kind)
+    };
+
+    let list = Value::from(vec![4.into(), vec![3.into()].into()]);
+    assert_eq!(call("main"), Ok(vec![list, 2.into()].into()));
+    assert_eq!(
+        call("out_of_range"),
+        Err(ErrorKind::IndexOutOfRange {
+            index: 2,
+            length: 2
+        })
+    );
+    assert_eq!(call("missing"), Err(ErrorKind::MissingField("y".into())));
+}
+
+#[test]
+fn test_cyclic_collection() {
+    use lorgn_lang::{
+        ast::{Expr, FnCall, Path},
+        parser::parse_module,
+    };
+
+    let source = r#"
+        export { graph };
+        fn graph() {
+            node = #{ name: "root", children: [] };
+            child = #{ name: "child", parent: node, children: [] };
+            node.children = [child, node];
+            child.children = [node];
+            loop_back = fn() { node };
+            node.loop_back = loop_back;
+            #{ root: node }
+        }
+    "#;
+    let mut runtime = Runtime::default();
+    runtime
+        .register(Module::from_ast("main", parse_module(source).unwrap()))
+        .unwrap();
+    let mut graph = || {
+        let call = Expr::FnCall(FnCall {
+            fn_path: Path {
+                module: "main".into(),
+                item: "graph".into(),
+            },
+            arguments: vec![],
+        });
+        runtime.evaluate(call).unwrap()
+    };
+    let kept = graph();
+    // unreachable cycles for the collector to reclaim.
+    for _ in 0..8 {
+        graph();
+    }
+    gc::force_collect();
+
+    let field = |value: &Value, name: &str| match value {
+        Value::Object(object) => object.borrow().get(&name.into()).cloned().unwrap(),
+        other => panic!("expected an object, found {}", other.type_name()),
+    };
+    let item = |value: &Value, index: usize| match value {
+        Value::List(list) => list.borrow()[index].clone(),
+        other => panic!("expected a list, found {}", other.type_name()),
+    };
+    let root = field(&kept, "root");
+    let child = item(&field(&root, "children"), 0);
+    assert_eq!(field(&child, "name"), "child".to_string().into());
+    let parent = field(&child, "parent");
+    let again = item(&field(&parent, "children"), 1);
+    assert_eq!(field(&again, "name"), "root".to_string().into());
+    let loop_back = field(&root, "loop_back");
+    let Value::Function(closure) = &loop_back else {
+        panic!("expected a function");
+    };
+    let captured = closure.captures.iter().find(|(name, _)| name.0 == "node");
+    let (_, captured) = captured.unwrap();
+    assert_eq!(field(captured, "name"), "root".to_string().into());
+}
+
+#[test]
+fn test_cyclic_equality() {
+    use lorgn_lang::parser::parse_expr;
+
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let mut runtime = Runtime::with_std();
+        runtime.set_engine(engine);
+        let mut eval = |source: &str| {
+            let expr = parse_expr(source).unwrap().expr;
+            runtime.evaluate(expr).unwrap()
+        };
+        assert_eq!(
+            eval("{ a = [1]; std::list::push(a, a); a == a }"),
+            true.into()
+        );
+        assert_eq!(
+            eval("{ a = [1]; std::list::push(a, a); b = [1]; std::list::push(b, b); a == b }"),
+            true.into()
+        );
+        assert_eq!(
+            eval("{ a = #{}; a.self = a; b = #{}; b.self = b; a == b }"),
+            true.into()
+        );
+        assert_eq!(
+            eval("{ a = #{}; a.self = a; b = #{ n: 1 }; b.self = b; a != b }"),
+            true.into()
+        );
+        assert_eq!(eval("[1, [2.0]] == [1.0, [2]]"), true.into());
+    }
+}
+
+#[test]
+fn test_cyclic_values() {
+    use lorgn_lang::parser::parse_expr;
+
+    let mut runtime = Runtime::with_std();
+    let mut eval = |source: &str| {
+        let expr = parse_expr(source).unwrap().expr;
+        runtime.evaluate(expr).unwrap()
+    };
+    let list = eval("{ l = [1]; std::list::push(l, l); l }");
+    assert_eq!(list, list.clone());
+    assert_eq!(format!("{list:?}"), "List([Integer(1), ...])");
+    let object = eval("{ o = #{ n: std::list::pop([]) }; o.self = o; o.f = fn() { o }; o }");
+    assert_eq!(object, object.clone());
+    assert_eq!(
+        format!("{object:?}"),
+        "Object({\"f\": Function(Closure { module: None, .. }), \"n\": None, \"self\": ...})"
+    );
+    assert_eq!(Value::from(1), Value::from(1.0));
+    assert_ne!(Value::from(1), Value::from("1".to_string()));
+}
+
+#[test]
+fn test_operators() {
+    use lorgn_lang::{
+        ast::{BinaryOperator, UnaryOperator},
+        parser::parse_expr,
+        typing::Primitive,
+    };
+
+    let mut runtime = Runtime::default();
+    let mut eval = |source: &str| {
+        let expr = parse_expr(source).unwrap().expr;
+        runtime.evaluate(expr).map_err(|e| e.kind)
+    };
+    assert_eq!(eval("1 + 2 * 3 - 4 % 3"), Ok(6.into()));
+    assert_eq!(eval("7 / 2"), Ok(3.into()));
+    assert_eq!(eval("1 + 0.5"), Ok(1.5.into()));
+    assert_eq!(eval("-(2) * 1.5"), Ok((-3.0).into()));
+    assert_eq!(eval("\"ab\" + \"c\""), Ok(Value::from("abc".to_string())));
+    assert_eq!(eval("1 == 1.0 and 2 != 3"), Ok(true.into()));
+    assert_eq!(eval("\"a\" < \"b\" and not (2 >= 2.5)"), Ok(true.into()));
+    assert_eq!(eval("0.0 / 0.0 < 1.0"), Ok(false.into()));
+    assert_eq!(eval("1 >= 0.0 / 0.0 or 0.0 / 0.0 > 1"), Ok(false.into()));
+    assert_eq!(eval("1.0 / 0.0 > 1"), Ok(true.into()));
+    assert_eq!(eval("[1, #{ x: 2 }] == [1, #{ x: 2 }]"), Ok(true.into()));
+    assert_eq!(eval("false and 1"), Ok(false.into()));
+    assert_eq!(eval("true or undefined"), Ok(true.into()));
+    assert_eq!(
+        eval("true and 1"),
+        Err(ErrorKind::TypeMismatch {
+            expected: "bool",
+            found: "integer"
+        })
+    );
+    assert_eq!(
+        eval("1 + \"a\""),
+        Err(ErrorKind::InvalidOperands {
+            operator: BinaryOperator::Add,
+            left: Primitive::Integer.into(),
+            right: Primitive::String.into(),
+        })
+    );
+    assert_eq!(
+        eval("0.0 / 0.0 < \"a\""),
+        Err(ErrorKind::InvalidOperands {
+            operator: BinaryOperator::Lt,
+            left: Primitive::Float.into(),
+            right: Primitive::String.into(),
+        })
+    );
+    assert_eq!(
+        eval("not 1"),
+        Err(ErrorKind::InvalidOperand {
+            operator: UnaryOperator::Not,
+            operand: Primitive::Integer.into(),
+        })
+    );
+    assert_eq!(eval("1 % 0"), Err(ErrorKind::DivisionByZero));
+}
+
+#[test]
+fn test_variadic_natives() {
+    use lorgn_lang::{parser::parse_expr, typing::Arity};
+
+    let mut runtime = Runtime::default();
+    let mut module = Module::new_empty("host");
+    module.push_variadic_native("count".into(), Arity::range(1, 2), |args| {
+        (args.len() as i32).into()
+    });
+    module.push_variadic_native("sum".into(), Arity::at_least(0), |args| {
+        args.into_iter()
+            .filter_map(Value::into_i32)
+            .sum::<i32>()
+            .into()
+    });
+    module.push_native("pair".into(), |[a, b]: [Value; 2]| vec![a, b].into());
+    runtime.register(module).unwrap();
+    let mut eval = |source: &str| {
+        let expr = parse_expr(source).unwrap().expr;
+        runtime.evaluate(expr).map_err(|e| e.kind)
+    };
+    assert_eq!(eval("host::count(1)"), Ok(1.into()));
+    assert_eq!(eval("host::count(1, 2)"), Ok(2.into()));
+    assert_eq!(eval("host::sum()"), Ok(0.into()));
+    assert_eq!(eval("host::sum(1, 2, 3, 4)"), Ok(10.into()));
+
+    let error = eval("host::count(1, 2, 3)").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "function 'count' expects 1 to 2 arguments but received 3"
+    );
+    // too many arguments for a fixed arity native is an error, not a panic.
+    assert_eq!(
+        eval("host::pair(1, 2, 3)"),
+        Err(ErrorKind::ArityMismatch {
+            function: "pair".into(),
+            expected: 2.into(),
+            found: 3,
+        })
+    );
+}
+
+#[test]
+fn test_typed_natives() {
+    use std::collections::HashMap;
+
+    use lorgn_lang::{
+        parser::{parse_expr, parse_module},
+        typing::{Primitive, Signature},
+    };
+
+    let mut runtime = Runtime::default();
+    let mut module = Module::new_empty("host");
+    let add = module.push_typed_native("add".into(), |a: i32, b: i32| a + b);
+    assert_eq!(
+        add.signature(),
+        Some(Signature::new(
+            vec![Primitive::Integer.into(), Primitive::Integer.into()],
+            Primitive::Integer.into()
+        ))
+    );
+    module.push_typed_native("half".into(), |x: f32| x / 2.0);
+    module.push_typed_native("offset".into(), |a: i64, b: usize| a + b as i64);
+    module.push_typed_native("giga".into(), |n: u8| n as u64 * 1_000_000_000);
+    module.push_typed_native("total".into(), |items: Vec<i32>| items.iter().sum::<i32>());
+    module.push_typed_native("names".into(), |object: HashMap<String, Value>| {
+        let mut names: Vec<String> = object.into_keys().collect();
+        names.sort();
+        names
+    });
+    module.push_typed_native("find".into(), |items: Vec<String>, item: String| {
+        items.iter().position(|i| i == &item).map(|i| i as i32)
+    });
+    module.push_typed_native("check".into(), |ok: bool| {
+        if ok {
+            Ok(())
+        } else {
+            Err("check failed")
+        }
+    });
+    let mut count = 0;
+    module.push_typed_native("count".into(), move || {
+        count += 1;
+        count
+    });
+    runtime.register(module).unwrap();
+    // integers are promoted where floats are expected.
+    let caller = parse_module("fn main() { host::half(3) + host::add(1, 2) }").unwrap();
+    assert_eq!(runtime.check("main", &caller), Ok(()));
+    let mut eval = |source: &str| {
+        let expr = parse_expr(source).unwrap().expr;
+        runtime.evaluate(expr).map_err(|e| e.kind)
+    };
+    assert_eq!(eval("host::add(1, 2)"), Ok(3.into()));
+    assert_eq!(eval("host::half(3)"), Ok(1.5.into()));
+    assert_eq!(eval("host::offset(-5, 2)"), Ok((-3).into()));
+    assert_eq!(
+        eval("host::offset(1, -1)"),
+        Err(ErrorKind::IntegerOutOfRange {
+            value: -1,
+            target: "usize"
+        })
+    );
+    assert_eq!(eval("host::giga(2)"), Ok(2_000_000_000.into()));
+    assert_eq!(
+        eval("host::giga(3)"),
+        Err(ErrorKind::IntegerOutOfRange {
+            value: 3_000_000_000,
+            target: "integer"
+        })
+    );
+    assert_eq!(
+        eval("host::giga(256)").map_err(|e| e.to_string()),
+        Err("integer 256 does not fit in u8".to_string())
+    );
+    assert_eq!(eval("host::total([1, 2, 3])"), Ok(6.into()));
+    assert_eq!(
+        eval("host::names(#{ b: 1, a: [] })"),
+        Ok(vec![Value::from("a".to_string()), Value::from("b".to_string())].into())
+    );
+    assert_eq!(eval("host::find([\"a\", \"b\"], \"b\")"), Ok(1.into()));
+    assert_eq!(eval("host::find([\"a\"], \"b\")"), Ok(Value::None));
+    assert_eq!(eval("host::check(true)"), Ok(Value::None));
+    assert_eq!(
+        eval("host::check(false)"),
+        Err(ErrorKind::Native("check failed".into()))
+    );
+    assert_eq!(eval("host::count() + host::count()"), Ok(3.into()));
+    assert_eq!(
+        eval("host::add(1, \"a\")"),
+        Err(ErrorKind::TypeMismatch {
+            expected: "integer",
+            found: "string"
+        })
+    );
+    assert_eq!(
+        eval("host::total([1, 2.5])"),
+        Err(ErrorKind::TypeMismatch {
+            expected: "integer",
+            found: "float"
+        })
+    );
+}
+
+#[test]
+fn test_user_data() {
+    use std::{cell::Cell, rc::Rc};
+
+    use gc::Gc;
+    use lorgn_lang::{parser::parse_expr, typing::Type};
+
+    struct Canvas {
+        width: i32,
+    }
+
+    let finalized = Rc::new(Cell::new(0));
+    let mut canvas = UserType::new("canvas");
+    canvas.push_method(Function::new_native(
+        "width".into(),
+        |[this]: [Value; 1]| {
+            let width = this
+                .as_user_data()
+                .and_then(|c| c.downcast_ref::<Canvas>().map(|c| c.width));
+            width.into()
         },
-        arguments: vec![Expr::Litteral("hello yorld".into()).boxed()],
-    }));
+    ));
+    let resize = |this: Gc<UserData>, width: i32| match this.downcast_mut::<Canvas>() {
+        Some(mut canvas) => {
+            canvas.width = width;
+            Ok(())
+        }
+        None => Err("not a canvas"),
+    };
+    canvas.push_method(resize.into_function("resize".into()));
+    let counter = finalized.clone();
+    canvas.set_finalizer(move |data| {
+        assert!(data.is::<Canvas>());
+        counter.set(counter.get() + 1);
+    });
+    let canvas = Rc::new(canvas);
+
+    let mut runtime = Runtime::default();
+    let mut module = Module::new_empty("host");
+    module.push_typed_native("canvas".into(), move |width: i32| {
+        UserData::new(canvas.clone(), Canvas { width })
+    });
+    runtime.register(module).unwrap();
+    let mut eval = |source: &str| {
+        let expr = parse_expr(source).unwrap().expr;
+        runtime.evaluate(expr).map_err(|e| e.kind)
+    };
+
+    let created = eval("host::canvas(3)").unwrap();
+    assert_eq!(created.type_of(), Type::UserData);
+    assert_eq!(created.type_name(), "canvas");
+    assert_eq!(created.to_string(), "<canvas>");
+    assert_eq!(created, created.clone());
+    assert_ne!(created, eval("host::canvas(3)").unwrap());
+    let user_data = created.as_user_data().unwrap();
+    assert!(user_data.is::<Canvas>());
+    assert_eq!(user_data.downcast_ref::<Canvas>().unwrap().width, 3);
+    assert!(user_data.downcast_ref::<String>().is_none());
+
+    assert_eq!(
+        eval("fn(c) { c.resize(5); c.width() }(host::canvas(3))"),
+        Ok(5.into())
+    );
+    assert_eq!(
+        eval("host::canvas(1).draw()"),
+        Err(ErrorKind::UnknownMethod {
+            type_name: "canvas",
+            method: "draw".into()
+        })
+    );
+    assert_eq!(
+        eval("host::canvas(1).width"),
+        Err(ErrorKind::TypeMismatch {
+            expected: "object",
+            found: "canvas"
+        })
+    );
+    assert_eq!(
+        eval("host::canvas(1).resize(\"a\")"),
+        Err(ErrorKind::TypeMismatch {
+            expected: "integer",
+            found: "string"
+        })
+    );
+    assert_eq!(eval("#{ f: fn() { 1 } }.f()"), Ok(1.into()));
+
+    drop(created);
+    gc::force_collect();
+    assert_eq!(finalized.get(), 6);
+}
+
+#[test]
+fn test_fuel_and_cancellation() {
+    use std::{thread, time::Duration};
+
+    use lorgn_lang::{ast::NodeId, parser::parse_expr};
+
+    let mut runtime = Runtime::default();
+    let eval = |runtime: &mut Runtime, source: &str| {
+        let node = parse_expr(source).unwrap();
+        runtime.evaluate(node.expr)
+    };
+
+    runtime.set_fuel(Some(100));
+    let error = eval(&mut runtime, "loop { 1 }").unwrap_err();
+    assert_eq!(error.kind, ErrorKind::OutOfFuel);
+    assert!(error.node.is_some());
+    assert_eq!(runtime.fuel(), Some(0));
+    runtime.set_fuel(Some(10));
+    assert_eq!(eval(&mut runtime, "1 + 2"), Ok(3.into()));
+    assert_eq!(runtime.fuel(), Some(7));
+    runtime.set_fuel(None);
+
+    // both engines consume fuel for each expression.
+    let source = r#"{
+        i = 0;
+        loop {
+            if i == 100 then break i else 0;
+            i = std::math::max(i, fn(x) { x + 1 }(i))
+        }
+    }"#;
+    let mut used = vec![];
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let mut runtime = Runtime::with_std();
+        runtime.set_engine(engine);
+        runtime.set_fuel(Some(10000));
+        assert_eq!(eval(&mut runtime, source), Ok(100.into()));
+        used.push(10000 - runtime.fuel().unwrap());
+    }
+    assert_eq!(used[0], used[1]);
+
+    let token = runtime.cancel_token();
+    let canceller = thread::spawn({
+        let token = token.clone();
+        move || {
+            thread::sleep(Duration::from_millis(20));
+            token.cancel();
+        }
+    });
+    let error = eval(&mut runtime, "loop { 1 }").unwrap_err();
+    canceller.join().unwrap();
+    assert_eq!(error.kind, ErrorKind::Cancelled);
+    assert_ne!(error.node, None);
+    assert_ne!(error.node, Some(NodeId(0)));
+    assert_eq!(
+        eval(&mut runtime, "1").map_err(|e| e.kind),
+        Err(ErrorKind::Cancelled)
+    );
+    token.reset();
+    assert_eq!(eval(&mut runtime, "1"), Ok(1.into()));
+}
+
+#[test]
+fn test_limits() {
+    use lorgn_lang::parser::{parse_expr, parse_module};
+
+    let source = r#"
+        export { down, grow };
+        fn down(n) { if n == 0 then 0 else main::down(n - 1) }
+        fn grow(s, n) { if n == 0 then s else main::grow(s + s, n - 1) }
+    "#;
+    let mut runtime = Runtime::default();
+    runtime
+        .register(Module::from_ast("main", parse_module(source).unwrap()))
+        .unwrap();
+    let eval = |runtime: &mut Runtime, source: &str| {
+        let expr = parse_expr(source).unwrap().expr;
+        runtime.evaluate(expr)
+    };
+
+    runtime.set_limits(Limits {
+        max_call_depth: Some(50),
+        ..Limits::default()
+    });
+    assert_eq!(eval(&mut runtime, "main::down(49)"), Ok(0.into()));
+    let error = eval(&mut runtime, "main::down(100)").unwrap_err();
+    assert_eq!(error.kind, ErrorKind::CallDepthExceeded(50));
+    assert!(error.node.is_some());
+    assert_eq!(
+        eval(&mut runtime, "fn(f) { f(f) }(fn(f) { f(f) })").map_err(|e| e.kind),
+        Err(ErrorKind::CallDepthExceeded(50))
+    );
+
+    runtime.set_limits(Limits {
+        max_scopes: Some(10),
+        ..Limits::default()
+    });
+    assert_eq!(
+        eval(&mut runtime, "main::down(20)").map_err(|e| e.kind),
+        Err(ErrorKind::ScopeLimitExceeded(10))
+    );
+
+    runtime.set_limits(Limits {
+        max_heap: Some(1000),
+        ..Limits::default()
+    });
+    assert_eq!(
+        eval(&mut runtime, "main::grow(\"ab\", 4)"),
+        Ok(Value::from("ab".repeat(16)))
+    );
+    let error = eval(&mut runtime, "main::grow(\"ab\", 20)").unwrap_err();
+    assert_eq!(error.kind, ErrorKind::HeapLimitExceeded(1000));
+    assert_eq!(
+        error.kind.to_string(),
+        "allocations exceeded the limit of 1000 bytes"
+    );
+    assert_eq!(
+        eval(&mut runtime, "[[1, 2, 3], [4, 5, 6]]").map(|v| v.to_string()),
+        Ok("[[1, 2, 3], [4, 5, 6]]".to_string())
+    );
+
+    // values built by natives are accounted for as well.
+    let mut runtime = Runtime::with_std();
+    runtime.set_limits(Limits {
+        max_heap: Some(1000),
+        ..Limits::default()
+    });
+    assert_eq!(
+        eval(&mut runtime, "std::list::range(0, 1000)").map_err(|e| e.kind),
+        Err(ErrorKind::HeapLimitExceeded(1000))
+    );
+
+    // as are lists and objects growing, the range being checked before it
+    // is built.
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        runtime.set_engine(engine);
+        assert_eq!(
+            eval(&mut runtime, "std::list::range(0, 500000000)").map_err(|e| e.kind),
+            Err(ErrorKind::HeapLimitExceeded(1000))
+        );
+        assert_eq!(
+            eval(&mut runtime, "{ l = []; loop { std::list::push(l, 1) } }").map_err(|e| e.kind),
+            Err(ErrorKind::HeapLimitExceeded(1000))
+        );
+        let fields = "{ o = #{}; i = 0; loop { o[std::convert::to_string(i)] = i; i = i + 1 } }";
+        assert_eq!(
+            eval(&mut runtime, fields).map_err(|e| e.kind),
+            Err(ErrorKind::HeapLimitExceeded(1000))
+        );
+        let fields = "{ o = #{}; loop { std::object::set(o, \"a\", 1); o.b = 2 } }";
+        runtime.set_fuel(Some(1000));
+        assert_eq!(
+            eval(&mut runtime, fields).map_err(|e| e.kind),
+            Err(ErrorKind::OutOfFuel)
+        );
+        runtime.set_fuel(None);
+
+        // values handed back by natives are not built again.
+        let source = r#"{
+            l = [std::list::range(1, 11)];
+            o = #{ l: l };
+            key = "l";
+            i = 0;
+            loop {
+                if i == 1000 then break i else 0;
+                std::list::get(l, 0);
+                std::object::get(o, key);
+                std::list::fold(l, 0, fn(acc, x) { x });
+                i = i + 1
+            }
+        }"#;
+        assert_eq!(eval(&mut runtime, source), Ok(1000.into()));
+    }
+}
+
+#[test]
+fn test_deep_nesting() {
+    use lorgn_lang::{
+        ast::{Expr, UnaryOp, UnaryOperator},
+        parser::{parse_expr, parse_module},
+    };
+
+    let source = r#"
+        export { down };
+        fn down(n) { if n == 0 then 0 else main::down(n - 1) }
+    "#;
+    let eval = |runtime: &mut Runtime, source: &str| {
+        let expr = parse_expr(source).unwrap().expr;
+        runtime.evaluate(expr)
+    };
+    let negated = |depth| {
+        let mut expr = parse_expr("1").unwrap().expr;
+        for _ in 0..depth {
+            expr = Expr::UnaryOp(UnaryOp {
+                operator: UnaryOperator::Neg,
+                operand: expr.boxed(),
+            });
+        }
+        expr
+    };
+    let nested = r#"{
+        l = [];
+        m = [];
+        i = 0;
+        loop {
+            if i == 20000 then break 0 else 0;
+            l = [l];
+            m = [m];
+            i = i + 1
+        };
+        [l == m, std::string::length(std::convert::to_string(l))]
+    }"#;
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let mut runtime = Runtime::with_std();
+        runtime.set_engine(engine);
+        runtime
+            .register(Module::from_ast("main", parse_module(source).unwrap()))
+            .unwrap();
+
+        // calls deeper than the stack of the host grow it.
+        runtime.set_limits(Limits {
+            max_call_depth: Some(20000),
+            ..Limits::default()
+        });
+        assert_eq!(eval(&mut runtime, "main::down(19999)"), Ok(0.into()));
+        assert_eq!(
+            eval(&mut runtime, "main::down(20010)").map_err(|e| e.kind),
+            Err(ErrorKind::CallDepthExceeded(20000))
+        );
+
+        // as do values nested without calls.
+        assert_eq!(
+            eval(&mut runtime, nested),
+            Ok(Value::from(vec![true.into(), 40002.into()]))
+        );
+
+        // expressions are checked before being evaluated.
+        assert_eq!(
+            runtime.evaluate(negated(1000)).map_err(|e| e.kind),
+            Err(ErrorKind::NestingTooDeep(512))
+        );
+        assert_eq!(runtime.evaluate(negated(510)), Ok(1.into()));
+        let deep = format!("export {{ f }}; fn f() {{ {}1 }}", "- ".repeat(600));
+        let module = Module::from_ast("deep", parse_module(&deep).unwrap());
+        assert_eq!(
+            runtime.register(module).map_err(|e| e.kind),
+            Err(ErrorKind::NestingTooDeep(512))
+        );
+        runtime.set_limits(Limits {
+            max_nesting: Some(5000),
+            ..Limits::default()
+        });
+        assert_eq!(runtime.evaluate(negated(4000)), Ok(1.into()));
+    }
+}
+
+#[test]
+fn test_stack_trace() {
+    use lorgn_lang::parser::{parse_expr, parse_module};
+
+    let source = r#"
+        export { main, each, show, absolute };
+        fn main() { main::outer(0) }
+        fn outer(n) {
+            f = fn(x) { main::inner(x) };
+            f(n)
+        }
+        fn inner(x) { 1 / x }
+        fn each(l) { std::list::map(l, fn(x) { main::inner(x) }) }
+        fn show() { std::io::print(1, 2) }
+        fn absolute() { std::math::abs("a") }
+    "#;
+    let mut runtime = Runtime::with_std();
+    runtime
+        .register(Module::from_ast("main", parse_module(source).unwrap()))
+        .unwrap();
+    let mut eval = |source: &str| {
+        let expr = parse_expr(source).unwrap().expr;
+        runtime.evaluate(expr).unwrap_err()
+    };
+
+    let error = eval("main::main()");
+    assert_eq!(error.kind, ErrorKind::DivisionByZero);
+    let frames = error.trace.frames();
+    let names: Vec<_> = frames
+        .iter()
+        .map(|frame| (frame.module.clone().unwrap().0, frame.function.0.clone()))
+        .collect();
+    let main = |function: &str| ("main".to_string(), function.to_string());
+    assert_eq!(
+        names,
+        vec![main("inner"), main("<lambda>"), main("outer"), main("main")]
+    );
+    assert_eq!(frames[0].node, error.node);
+    assert!(frames.iter().all(|frame| frame.node.is_some()));
+    let rendered = error.trace.to_string();
+    let lines: Vec<_> = rendered.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("  at main::inner (node #"));
+    assert!(lines[1].starts_with("  at main::<lambda> (node #"));
+
+    let error = eval("main::each([1, 0])");
+    let names: Vec<_> = error
+        .trace
+        .frames()
+        .iter()
+        .map(|frame| frame.function.0.as_str())
+        .collect();
+    assert_eq!(names, vec!["inner", "<lambda>", "map", "each"]);
+    assert_eq!(error.trace.frames()[2].module, Some("std::list".into()));
+
+    // natives show in the trace whatever their kind.
+    let calls = [
+        ("main::show()", ["print", "show"]),
+        ("main::absolute()", ["abs", "absolute"]),
+    ];
+    for (call, expected) in calls {
+        let error = eval(call);
+        let names: Vec<_> = error
+            .trace
+            .frames()
+            .iter()
+            .map(|frame| frame.function.0.as_str())
+            .collect();
+        assert_eq!(names, expected);
+    }
+
+    let error = eval("1 / 0");
+    assert!(error.trace.is_empty());
+}
+
+#[test]
+fn test_scoping() {
+    use lorgn_lang::parser::{parse_expr, parse_module};
+
+    let source = "export { read }; fn read() { x }";
+    let mut runtime = Runtime::default();
+    runtime
+        .register(Module::from_ast("main", parse_module(source).unwrap()))
+        .unwrap();
+    let mut eval = |source: &str| {
+        let expr = parse_expr(source).unwrap().expr;
+        runtime.evaluate(expr).map_err(|e| e.kind)
+    };
+
+    // top-level variables persist across evaluations.
+    assert_eq!(eval("x = 1"), Ok(1.into()));
+    assert_eq!(eval("x = x + 1"), Ok(2.into()));
+    assert_eq!(eval("let z = x * 10"), Ok(20.into()));
+    assert_eq!(eval("z"), Ok(20.into()));
+    // functions do not see the global scope, closures capture it.
+    assert_eq!(
+        eval("main::read()"),
+        Err(ErrorKind::UndefinedVariable("x".into()))
+    );
+    assert_eq!(eval("fn() { x }()"), Ok(2.into()));
+
+    // blocks read and update outer variables, their own ones ending with them.
+    assert_eq!(eval("{ a = 1; { a = a + 1; b = 1 }; a }"), Ok(2.into()));
+    assert_eq!(
+        eval("{ { b = 1 }; b }"),
+        Err(ErrorKind::UndefinedVariable("b".into()))
+    );
+    assert_eq!(eval("{ y = 1 }"), Ok(1.into()));
+    assert_eq!(eval("y"), Err(ErrorKind::UndefinedVariable("y".into())));
+
+    // a declaration shadows the outer variable until the end of its block.
+    assert_eq!(
+        eval("{ b = 1; { let b = 10; b = b + 1 }; b }"),
+        Ok(1.into())
+    );
+    assert_eq!(
+        eval("{ let x = \"inner\"; x }"),
+        Ok(Value::from("inner".to_string()))
+    );
+    assert_eq!(eval("x"), Ok(2.into()));
+
+    assert_eq!(
+        eval("{ i = 0; loop { i = i + 1; if i == 3 then break i else 0 } }"),
+        Ok(3.into())
+    );
+
+    assert_eq!(runtime.global(&"x".into()), Some(&2.into()));
+    runtime.set_global("w".into(), 7.into());
+    let expr = parse_expr("w + x").unwrap().expr;
+    assert_eq!(runtime.evaluate(expr), Ok(9.into()));
+}
+
+#[test]
+fn test_module_items() {
+    use lorgn_lang::{
+        ast::Path,
+        parser::{parse_expr, parse_module},
+    };
+
+    let source = r#"
+        export { LIMITS, next, shadow, reset };
+        const LIMITS = #{ low: 1, high: 2 * 3 };
+        global count = LIMITS.low;
+
+        fn next() {
+            count = count + 1;
+            if count > LIMITS.high then count = LIMITS.low else count
+        }
+        fn shadow(count) { count = 10; config::count }
+        fn reset() { LIMITS = 0 }
+    "#;
+    let mut runtime = Runtime::default();
+    runtime
+        .register(Module::from_ast("config", parse_module(source).unwrap()))
+        .unwrap();
+    let mut host = Module::new_empty("host");
+    host.push_const("VERSION".into(), 3.into());
+    runtime.register(host).unwrap();
+
+    let path = |module: &str, item: &str| Path {
+        module: module.into(),
+        item: item.into(),
+    };
+    let mut eval = |source: &str| {
+        let expr = parse_expr(source).unwrap().expr;
+        runtime.evaluate(expr).map_err(|e| e.kind)
+    };
+    assert_eq!(eval("config::LIMITS.high"), Ok(6.into()));
+    assert_eq!(eval("host::VERSION"), Ok(3.into()));
+    assert_eq!(eval("config::next() * 10 + config::next()"), Ok(23.into()));
+    // parameters hide the globals of the same name.
+    assert_eq!(eval("config::shadow(0)"), Ok(3.into()));
+    assert_eq!(
+        eval("config::reset()"),
+        Err(ErrorKind::ConstAssignment("LIMITS".into()))
+    );
+    assert_eq!(
+        eval("config::count"),
+        Err(ErrorKind::PrivateItem(path("config", "count")))
+    );
+    assert_eq!(
+        eval("config::missing"),
+        Err(ErrorKind::UnknownItem(path("config", "missing")))
+    );
+    // constants cannot be reassigned, the objects they hold stay mutable.
+    assert_eq!(eval("config::LIMITS.high = 4"), Ok(4.into()));
+    assert_eq!(eval("[config::next(), config::next()]"), eval("[4, 1]"));
+    assert_eq!(runtime.item(&path("config", "count")), Some(1.into()));
+
+    // initializers only see the items defined before them, a failing one
+    // leaving the module unregistered.
+    let source = "const A = B + 1; const B = 1;";
+    let module = Module::from_ast("broken", parse_module(source).unwrap());
+    let error = runtime.register(module).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::UndefinedVariable("B".into()));
+    assert_eq!(error.trace.frames()[0].function, "A".into());
+    assert!(runtime.item(&path("broken", "B")).is_none());
+    let expr = parse_expr("broken::A").unwrap().expr;
+    assert_eq!(
+        runtime.evaluate(expr).map_err(|e| e.kind),
+        Err(ErrorKind::UnknownModule("broken".into()))
+    );
+}
+
+#[test]
+fn test_host_calls() {
+    use lorgn_lang::{ast::Path, parser::parse_module};
+
+    let source = r#"
+        import std::math::{max};
+        export { scale, label, max, hidden_user };
+        global calls = 0;
+
+        fn scale(x: integer, factor) { calls = calls + 1; x * factor }
+        fn label(name, count) { name + ": " + std::convert::to_string(count) }
+        fn hidden() { 0 }
+        fn hidden_user() { main::hidden() }
+    "#;
+    let mut runtime = Runtime::with_std();
+    runtime
+        .register(Module::from_ast("main", parse_module(source).unwrap()))
+        .unwrap();
+    let path = |module: &str, item: &str| Path {
+        module: module.into(),
+        item: item.into(),
+    };
+
+    assert_eq!(
+        runtime.call(&path("main", "scale"), vec![2.into(), 3.into()]),
+        Ok(6.into())
+    );
+    let label: String = runtime
+        .call_typed(&path("main", "label"), ("total", 4))
+        .unwrap();
+    assert_eq!(label, "total: 4");
+    let error = runtime.call(&path("main", "hidden"), vec![]).unwrap_err();
+    assert_eq!(
+        error.kind,
+        ErrorKind::PrivateFunction(path("main", "hidden"))
+    );
+    assert_eq!(
+        runtime.call(&path("main", "hidden_user"), vec![]),
+        Ok(0.into())
+    );
+    let error = runtime
+        .call_typed::<i32>(&path("main", "label"), ("a", 1))
+        .unwrap_err();
+    assert_eq!(
+        error.kind,
+        ErrorKind::TypeMismatch {
+            expected: "integer",
+            found: "string",
+        }
+    );
+    let error = runtime
+        .call(&path("main", "scale"), vec![1.into()])
+        .unwrap_err();
+    assert_eq!(
+        error.kind,
+        ErrorKind::ArityMismatch {
+            function: "scale".into(),
+            expected: 2.into(),
+            found: 1,
+        }
+    );
+
+    // handles follow imports and stay valid across calls.
+    let scale = runtime.function_handle(&path("main", "scale")).unwrap();
+    let total: i32 = (1..=3)
+        .map(|x| scale.call_typed::<i32>(&mut runtime, (x, 10)).unwrap())
+        .sum();
+    assert_eq!(total, 60);
+    assert_eq!(runtime.item(&path("main", "calls")), Some(4.into()));
+    let max = runtime.function_handle(&path("main", "max")).unwrap();
+    assert_eq!(max.module(), &"std::math".into());
+    assert_eq!(
+        max.call(&mut runtime, vec![1.into(), 5.into()]),
+        Ok(5.into())
+    );
+    assert_eq!(
+        runtime
+            .function_handle(&path("main", "hidden"))
+            .unwrap_err()
+            .kind,
+        ErrorKind::PrivateFunction(path("main", "hidden"))
+    );
+    assert_eq!(
+        runtime
+            .function_handle(&path("other", "f"))
+            .unwrap_err()
+            .kind,
+        ErrorKind::UnknownModule("other".into())
+    );
+}
+
+#[test]
+fn test_engines() {
+    use lorgn_lang::{
+        ast::Path,
+        parser::{parse_expr, parse_module},
+    };
+
+    let source = r#"
+        export { fib, count, adder, apply, fold, shapes, fails, traced, bump, total, brk, typed };
+        global total = 0;
+        const STEP = 2;
+
+        fn fib(n) { if n < 2 then n else main::fib(n - 1) + main::fib(n - 2) }
+        fn count(n) {
+            i = 0;
+            loop { i = i + 1; if i >= n then break i else { let i = 0; i } }
+        }
+        fn adder(x) { fn(y) { x + y } }
+        fn apply(f, x) { f(x) }
+        fn fold(l) {
+            sum = 0;
+            i = 0;
+            loop {
+                if i == std::list::length(l) then return sum else 0;
+                sum = sum + l[i];
+                i = i + 1
+            }
+        }
+        fn shapes() {
+            o = #{ sides: [3, 4], name: "square" };
+            o.sides[1] = 5;
+            o.area = fn(s) { s * s };
+            [o.area(o.sides[1]), o.name + "!", true and not false or 1 == 2]
+        }
+        fn fails(x) { if x > 0 then 1 / 0 else missing }
+        fn traced() { std::list::map([1, 0], fn(x) { 1 / x }) }
+        fn bump() { total = total + STEP; total }
+        fn brk() { break 1 }
+        fn typed(n: integer) -> string { n }
+    "#;
+    let expressions = [
+        "main::fib(15)",
+        "main::count(10)",
+        "main::apply(main::adder(3), 4)",
+        "{ f = main::adder(10); [f(1), f(2)] }",
+        "main::fold([1, 2, 3, 4])",
+        "main::shapes()",
+        "[main::bump(), main::bump(), main::total]",
+        "{ x = 1; g = fn() { x = x + 1; x }; [g(), g(), x] }",
+        "{ n = 0; loop { n = n + 1; if n == 5 then break n * 2 else { m = n } } }",
+        "{ a = 1; { let a = 2; b = a }; a }",
+        "y = 3",
+        "y * 2",
+        "main::fails(1)",
+        "main::fails(0)",
+        "main::traced()",
+        "main::STEP",
+        "main::count(\"a\")",
+        "false or 1",
+        "main::nothing()",
+        "main::brk()",
+        "fn() { break 2 }()",
+        "main::fib()",
+        "main::typed(\"a\")",
+        "main::typed(1)",
+    ];
+    let mut runtimes = [Engine::TreeWalker, Engine::Bytecode].map(|engine| {
+        let mut runtime = Runtime::with_std();
+        runtime.set_engine(engine);
+        runtime
+            .register(Module::from_ast("main", parse_module(source).unwrap()))
+            .unwrap();
+        runtime
+    });
+    for source in expressions {
+        let results = runtimes.each_mut().map(|runtime| {
+            let expr = parse_expr(source).unwrap().expr;
+            runtime.evaluate(expr)
+        });
+        assert_eq!(results[0], results[1], "{source}");
+    }
+    // errors of a call are traced from the callee.
+    for (source, function) in [
+        ("main::brk()", "brk"),
+        ("main::fib()", "fib"),
+        ("main::typed(1)", "typed"),
+    ] {
+        for runtime in &mut runtimes {
+            let expr = parse_expr(source).unwrap().expr;
+            let error = runtime.evaluate(expr).unwrap_err();
+            let frame = &error.trace.frames()[0];
+            assert_eq!(frame.function, function.into(), "{source}");
+            assert!(frame.node.is_some(), "{source}");
+        }
+    }
+    let [_, bytecode] = &mut runtimes;
+    assert_eq!(bytecode.engine(), Engine::Bytecode);
+
+    // calls resolved by compiled code follow the modules registered later.
+    let path = Path {
+        module: "main".into(),
+        item: "caller".into(),
+    };
+    let caller = |value: i32| {
+        let source = format!(
+            "export {{ caller, value }}; fn caller() {{ main::value() }} fn value() {{ {value} }}"
+        );
+        Module::from_ast("main", parse_module(&source).unwrap())
+    };
+    bytecode.register(caller(1)).unwrap();
+    assert_eq!(bytecode.call(&path, vec![]), Ok(1.into()));
+    bytecode.register(caller(2)).unwrap();
+    assert_eq!(bytecode.call(&path, vec![]), Ok(2.into()));
 }
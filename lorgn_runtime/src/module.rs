@@ -1,66 +1,234 @@
 use std::{
-    cell::{RefCell, RefMut},
+    cell::RefCell,
     collections::{HashMap, HashSet},
+    rc::Rc,
 };
 
-use lorgn_lang::ast::{self, Name, TopLevel};
+use lorgn_lang::{
+    ast::{self, Name, TopLevel, ValueDef},
+    typing::Arity,
+};
+
+use crate::{runtime::Context, ErrorKind, Function, RuntimeError, TypedNative, Value};
 
-use crate::{Function, Value};
+/// constant or global of a module.
+#[derive(Debug)]
+struct Item {
+    value: Value,
+    mutable: bool,
+}
 
 #[derive(Debug)]
 pub struct Module {
     name: Name,
-    functions: HashMap<Name, RefCell<Function>>,
-    _exports: HashSet<Name>, // TODO
+    /// functions shared with the handles resolved to them.
+    functions: HashMap<Name, Rc<Function>>,
+    exports: HashSet<Name>,
+    /// constants and globals, in a cell as globals are assigned while the
+    /// module is shared.
+    items: RefCell<HashMap<Name, Item>>,
+    /// definitions of the items not evaluated yet, along with whether they
+    /// are globals.
+    initializers: Vec<(ValueDef, bool)>,
+    /// names of the functions and imports defined more than once.
+    duplicates: Vec<Name>,
 }
 
 impl Module {
     pub fn new_empty(name: impl ToString) -> Self {
         let name = name.to_string().into();
         Self {
-            _exports: HashSet::new(),
+            exports: HashSet::new(),
             functions: HashMap::new(),
+            items: RefCell::default(),
+            initializers: vec![],
+            duplicates: vec![],
             name,
         }
     }
-    pub fn from_ast(name: impl ToString, content: ast::Module) -> Self {
+    pub fn from_ast(name: impl ToString, mut content: ast::Module) -> Self {
         let name = name.to_string().into();
+        content.assign_ids();
         let mut functions = HashMap::new();
         let mut exports = HashSet::new();
+        let mut initializers = vec![];
+        let mut duplicates = vec![];
+        let mut insert = |name: Name, function: Function| {
+            if functions.insert(name.clone(), Rc::new(function)).is_some() {
+                duplicates.push(name);
+            }
+        };
 
         for item in content.items {
             match item {
+                TopLevel::Import(import) => {
+                    for name in import.items {
+                        let module = import.module_name.clone();
+                        let fun = Function::new_imported(name.clone(), module);
+                        insert(name, fun);
+                    }
+                }
                 TopLevel::Export(export) => export.items.iter().for_each(|e| {
                     exports.insert(e.clone());
                 }),
                 TopLevel::FnDef(fndef) => {
                     let name = fndef.name.clone();
                     let fun = Function::new_defined(name.clone(), fndef);
-                    functions.insert(name, RefCell::new(fun));
+                    insert(name, fun);
                 }
+                TopLevel::Const(definition) => initializers.push((definition, false)),
+                TopLevel::Global(definition) => initializers.push((definition, true)),
             };
         }
 
         Self {
             name,
             functions,
-            _exports: exports,
+            exports,
+            items: RefCell::default(),
+            initializers,
+            duplicates,
         }
     }
     pub fn push_native<const N: usize>(
         &mut self,
         name: Name,
         caller: impl FnMut([Value; N]) -> Value + 'static,
-    ) {
-        let nat = Function::new_native(name.clone(), caller);
-        self.functions.insert(name, RefCell::new(nat));
+    ) -> &mut Function {
+        let nat = Function::new_native(name, caller);
+        self.insert_function(nat)
+    }
+
+    /// adds a native given the calling context, to call back into scripts,
+    /// raise errors or account for the values it builds with
+    /// `Context::allocate`.
+    pub fn push_contextual_native<const N: usize>(
+        &mut self,
+        name: Name,
+        caller: impl Fn(&mut Context, [Value; N]) -> Result<Value, RuntimeError> + 'static,
+    ) -> &mut Function {
+        let nat = Function::new_contextual_native(name, caller);
+        self.insert_function(nat)
+    }
+
+    /// adds a native taking a number of arguments within `arity`.
+    pub fn push_variadic_native(
+        &mut self,
+        name: Name,
+        arity: Arity,
+        caller: impl FnMut(Vec<Value>) -> Value + 'static,
+    ) -> &mut Function {
+        let nat = Function::new_variadic_native(name, arity, caller);
+        self.insert_function(nat)
+    }
+
+    pub fn push_contextual_variadic_native(
+        &mut self,
+        name: Name,
+        arity: Arity,
+        caller: impl Fn(&mut Context, Vec<Value>) -> Result<Value, RuntimeError> + 'static,
+    ) -> &mut Function {
+        let nat = Function::new_contextual_variadic_native(name, arity, caller);
+        self.insert_function(nat)
+    }
+
+    /// adds a rust function as a native, converting its arguments and result
+    /// and declaring the matching signature.
+    pub fn push_typed_native<Args>(
+        &mut self,
+        name: Name,
+        function: impl TypedNative<Args>,
+    ) -> &mut Function {
+        let nat = function.into_function(name);
+        self.insert_function(nat)
+    }
+
+    /// adds a function and exports it, returning it to be further set up.
+    fn insert_function(&mut self, function: Function) -> &mut Function {
+        let name = function.name().clone();
+        self.export(name.clone());
+        let function = self.functions.entry(name).insert_entry(Rc::new(function));
+        // the function was just created, nothing else refers to it yet.
+        Rc::get_mut(function.into_mut()).unwrap()
+    }
+
+    /// adds a constant the scripts can read.
+    pub fn push_const(&mut self, name: Name, value: Value) {
+        self.export(name.clone());
+        self.define_item(name, value, false);
+    }
+
+    /// adds a global the scripts can read and assign.
+    pub fn push_global(&mut self, name: Name, value: Value) {
+        self.export(name.clone());
+        self.define_item(name, value, true);
+    }
+
+    pub(crate) fn define_item(&self, name: Name, value: Value, mutable: bool) {
+        let item = Item { value, mutable };
+        self.items.borrow_mut().insert(name, item);
+    }
+
+    /// definitions of the constants and globals, to be evaluated in order
+    /// when the module is registered.
+    pub(crate) fn take_initializers(&mut self) -> Vec<(ValueDef, bool)> {
+        std::mem::take(&mut self.initializers)
+    }
+
+    /// current value of a constant or global.
+    pub fn get_item(&self, name: &Name) -> Option<Value> {
+        self.items.borrow().get(name).map(|item| item.value.clone())
+    }
+
+    pub fn has_item(&self, name: &Name) -> bool {
+        self.items.borrow().contains_key(name)
+    }
+
+    /// assigns a global, failing for constants.
+    pub(crate) fn set_item(&self, name: &Name, value: Value) -> Result<(), RuntimeError> {
+        let mut items = self.items.borrow_mut();
+        match items.get_mut(name) {
+            Some(item) if item.mutable => {
+                item.value = value;
+                Ok(())
+            }
+            _ => Err(ErrorKind::ConstAssignment(name.clone()).into()),
+        }
+    }
+
+    /// first name given to several functions or imports, which would
+    /// otherwise replace one another.
+    pub(crate) fn duplicate(&self) -> Option<&Name> {
+        self.duplicates.first()
+    }
+
+    pub fn export(&mut self, name: Name) {
+        self.exports.insert(name);
+    }
+
+    pub fn is_exported(&self, name: &Name) -> bool {
+        self.exports.contains(name)
     }
 
     pub fn name(&self) -> &Name {
         &self.name
     }
 
-    pub fn get_function(&self, name: &Name) -> Option<RefMut<Function>> {
-        self.functions.get(name).map(|entry| entry.borrow_mut())
+    pub fn get_function(&self, name: &Name) -> Option<&Function> {
+        self.functions.get(name).map(Rc::as_ref)
+    }
+
+    pub(crate) fn shared_function(&self, name: &Name) -> Option<Rc<Function>> {
+        self.functions.get(name).cloned()
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.functions.values().map(Rc::as_ref)
+    }
+
+    /// functions callable from other modules.
+    pub fn public_functions(&self) -> impl Iterator<Item = &Function> {
+        self.functions()
+            .filter(|function| self.is_exported(function.name()))
     }
 }
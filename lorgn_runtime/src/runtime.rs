@@ -1,38 +1,401 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
-use lorgn_lang::ast::{Expr, Name};
+use lorgn_lang::{
+    ast::{self, Expr, Name, Path},
+    typing::{check_module, Signatures, TypeError},
+};
 
-use crate::{Module, Value};
+use crate::{
+    stdlib, ErrorKind, FromValue, Function, FunctionHandle, IntoArgs, Module, RuntimeError, Value,
+};
+
+/// flag stopping the evaluations of a runtime, which can be set from
+/// another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// allows evaluations again after a cancellation.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// bounds on the resources used by an evaluation, `None` when unbounded.
+///
+/// evaluations grow the stack of the host as they recurse, so deep calls do
+/// not overflow it but take a few kilobytes of memory per call, outside of
+/// `max_heap`. the nesting of expressions is bounded by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// number of nested calls of functions and closures.
+    pub max_call_depth: Option<usize>,
+    /// number of scopes alive at once.
+    pub max_scopes: Option<usize>,
+    /// approximate number of bytes of strings, lists and objects allocated,
+    /// including the items and fields added to them. natives account for the
+    /// values they build through `Context::allocate`, typed natives doing so
+    /// for the strings, lists and objects they return.
+    pub max_heap: Option<usize>,
+    /// depth of the expressions of a module or an evaluation, checked before
+    /// they run.
+    pub max_nesting: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_call_depth: None,
+            max_scopes: None,
+            max_heap: None,
+            max_nesting: Some(DEFAULT_MAX_NESTING),
+        }
+    }
+}
+
+/// nesting of expressions checked by default, deep enough for scripts
+/// written by hand.
+const DEFAULT_MAX_NESTING: usize = 512;
+
+/// stack left before growing it, enough for the deepest frame of an
+/// evaluation.
+const RED_ZONE: usize = 64 * 1024;
+/// size of each stack segment added.
+const STACK_SIZE: usize = 1024 * 1024;
+
+/// runs `f` on a new stack segment when the current one is almost full.
+pub(crate) fn grow_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, STACK_SIZE, f)
+}
+
+/// fails when expressions are nested deeper than `max`, walking them without
+/// recursion.
+fn check_nesting<'e>(
+    expressions: impl IntoIterator<Item = &'e Expr>,
+    max: Option<usize>,
+) -> Result<(), RuntimeError> {
+    let Some(max) = max else {
+        return Ok(());
+    };
+    let mut pending: Vec<_> = expressions.into_iter().map(|expr| (expr, 1)).collect();
+    while let Some((expr, depth)) = pending.pop() {
+        for child in expr.children() {
+            if depth >= max {
+                let error = RuntimeError::from(ErrorKind::NestingTooDeep(max));
+                return Err(error.at(child.meta.id));
+            }
+            pending.push((&child.expr, depth + 1));
+        }
+    }
+    Ok(())
+}
+
+/// how scripts are evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Engine {
+    /// walks the syntax tree of the scripts.
+    #[default]
+    TreeWalker,
+    /// compiles functions to bytecode on their first call, run by a virtual
+    /// machine.
+    Bytecode,
+}
 
 pub struct Runtime {
     modules: HashMap<Name, Module>,
+    /// number of modules registered so far, telling when resolved functions
+    /// may be stale.
+    generation: u64,
+    engine: Engine,
+    /// number of expressions left to evaluate, `None` when unlimited.
+    fuel: Option<u64>,
+    cancel_token: CancelToken,
+    limits: Limits,
+    /// variables of the top-level evaluations, kept from one to the next.
+    globals: Scope,
 }
 
 impl Default for Runtime {
     fn default() -> Self {
         let modules = HashMap::new();
-        Self { modules }
+        Self {
+            modules,
+            generation: 0,
+            engine: Engine::default(),
+            fuel: None,
+            cancel_token: CancelToken::default(),
+            limits: Limits::default(),
+            globals: Scope::new(false),
+        }
     }
 }
 
 impl Runtime {
-    pub fn register(&mut self, module: Module) {
-        self.modules.insert(module.name().clone(), module);
+    /// runtime with the modules of the standard library registered.
+    pub fn with_std() -> Self {
+        let mut result = Self::default();
+        for module in stdlib::modules() {
+            result.modules.insert(module.name().clone(), module);
+        }
+        result
+    }
+
+    /// adds a module once its imports are checked and its constants and
+    /// globals evaluated, leaving the runtime unchanged when either fails.
+    pub fn register(&mut self, mut module: Module) -> Result<(), RuntimeError> {
+        if let Some(item) = module.duplicate() {
+            let path = Path {
+                module: module.name().clone(),
+                item: item.clone(),
+            };
+            return Err(ErrorKind::DuplicateItem(path).into());
+        }
+        for function in module.functions() {
+            if let Some(path) = function.imported_path() {
+                self.check_import(&module, path)?;
+            }
+        }
+        let bodies = module.functions().filter_map(Function::definition);
+        let expressions = bodies.flat_map(|definition| &definition.expressions.expressions);
+        check_nesting(expressions.map(|node| &node.expr), self.limits.max_nesting)?;
+        let initializers = module.take_initializers();
+        let values = initializers
+            .iter()
+            .map(|(definition, _)| &definition.value.expr);
+        check_nesting(values, self.limits.max_nesting)?;
+        let name = module.name().clone();
+        let previous = self.modules.insert(name.clone(), module);
+        self.generation += 1;
+        if let Err(error) = self.context().initialize(&name, &initializers) {
+            match previous {
+                Some(previous) => self.modules.insert(name, previous),
+                None => self.modules.remove(&name),
+            };
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// follows a chain of imports until it reaches a concrete function,
+    /// `pending` standing for the module being registered.
+    fn check_import(&self, pending: &Module, path: &Path) -> Result<(), RuntimeError> {
+        let mut visited = HashSet::new();
+        let mut requester = pending.name();
+        let mut current = path;
+        loop {
+            if !visited.insert(current) {
+                return Err(ErrorKind::ImportCycle(path.clone()).into());
+            }
+            let module = if &current.module == pending.name() {
+                pending
+            } else {
+                self.modules
+                    .get(&current.module)
+                    .ok_or_else(|| ErrorKind::UnknownModule(current.module.clone()))?
+            };
+            let function = module
+                .get_function(&current.item)
+                .ok_or_else(|| ErrorKind::UnknownFunction(current.clone()))?;
+            if &current.module != requester && !module.is_exported(&current.item) {
+                return Err(ErrorKind::PrivateFunction(current.clone()).into());
+            }
+            match function.imported_path() {
+                Some(next) => {
+                    requester = &current.module;
+                    current = next;
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// signatures of every public function of the registered modules.
+    pub fn signatures(&self) -> Signatures {
+        let mut signatures = Signatures::new();
+        for module in self.modules.values() {
+            for function in module.public_functions() {
+                let mut target = function;
+                while let Some(path) = target.imported_path() {
+                    match self.function(path) {
+                        Some(next) => target = next,
+                        None => break,
+                    }
+                }
+                if let Some(signature) = target.signature() {
+                    let path = Path {
+                        module: module.name().clone(),
+                        item: function.name().clone(),
+                    };
+                    signatures.insert(path, signature);
+                }
+            }
+        }
+        signatures
+    }
+
+    /// type checks a module against the registered ones before it is
+    /// registered itself.
+    pub fn check(&self, name: impl ToString, module: &ast::Module) -> Result<(), Vec<TypeError>> {
+        let name = name.to_string().into();
+        let errors = check_module(&name, module, &self.signatures());
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn function(&self, path: &Path) -> Option<&Function> {
+        self.modules.get(&path.module)?.get_function(&path.item)
+    }
+
+    /// calls an exported function from the host.
+    pub fn call(&mut self, path: &Path, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        self.context().call_function(path, args)
+    }
+
+    /// calls an exported function with converted arguments, converting its
+    /// result.
+    pub fn call_typed<R: FromValue>(
+        &mut self,
+        path: &Path,
+        args: impl IntoArgs,
+    ) -> Result<R, RuntimeError> {
+        R::from_value(self.call(path, args.into_args()?)?)
+    }
+
+    /// resolves an exported function, following imports, into a handle
+    /// which can be called repeatedly.
+    pub fn function_handle(&self, path: &Path) -> Result<FunctionHandle, RuntimeError> {
+        resolve_function(&self.modules, None, path)
+    }
+
+    /// current value of a constant or global of a registered module.
+    pub fn item(&self, path: &Path) -> Option<Value> {
+        self.modules.get(&path.module)?.get_item(&path.item)
+    }
+
+    /// evaluates an expression in the global scope, where its top-level
+    /// variables stay for the next evaluations.
+    pub fn evaluate(&mut self, expression: Expr) -> Result<Value, RuntimeError> {
+        check_nesting([&expression], self.limits.max_nesting)?;
+        let globals = std::mem::replace(&mut self.globals, Scope::new(false));
+        let mut context = self.context().with_globals(globals);
+        let result = context.run_expr(&expression);
+        self.globals = context.into_globals();
+        result
+    }
+
+    pub fn global(&self, name: &Name) -> Option<&Value> {
+        self.globals.get(name)
+    }
+
+    pub fn set_global(&mut self, name: Name, value: Value) {
+        self.globals.insert(name, value);
     }
 
-    pub fn evaluate(&mut self, expression: Expr) -> Value {
-        let mut context = self.context();
-        context.run_expr(&expression)
+    /// sets the number of expressions which can still be evaluated, across
+    /// evaluations, `None` removing the limit.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
     }
 
-    fn context(&mut self) -> Context {
-        Context::new(&mut self.modules)
+    /// number of expressions which can still be evaluated.
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// token cancelling the running and later evaluations until it is reset.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel_token.clone()
+    }
+
+    /// sets the limits each evaluation is checked against.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// sets how the next evaluations run the scripts.
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    pub(crate) fn context(&mut self) -> Context<'_> {
+        let context = Context::new(&mut self.modules)
+            .with_cancel_token(self.cancel_token.clone())
+            .with_limits(self.limits)
+            .with_engine(self.engine, self.generation);
+        match &mut self.fuel {
+            Some(fuel) => context.with_fuel(fuel),
+            None => context,
+        }
     }
 }
 
+/// resolves a function callable from `requester`, following imports.
+fn resolve_function(
+    modules: &HashMap<Name, Module>,
+    requester: Option<&Name>,
+    path: &Path,
+) -> Result<FunctionHandle, RuntimeError> {
+    let module = modules
+        .get(&path.module)
+        .ok_or_else(|| ErrorKind::UnknownModule(path.module.clone()))?;
+    let mut function = module
+        .shared_function(&path.item)
+        .ok_or_else(|| ErrorKind::UnknownFunction(path.clone()))?;
+    if requester != Some(&path.module) && !module.is_exported(&path.item) {
+        return Err(ErrorKind::PrivateFunction(path.clone()).into());
+    }
+    let mut module = path.module.clone();
+    // imports were checked when their module was registered.
+    while let Some(next) = function.imported_path() {
+        let next = next.clone();
+        function = modules
+            .get(&next.module)
+            .and_then(|module| module.shared_function(&next.item))
+            .ok_or_else(|| ErrorKind::UnknownFunction(next.clone()))?;
+        module = next.module;
+    }
+    Ok(FunctionHandle::new(module, function))
+}
+
 pub use eval_result::EvRes;
 mod eval_result;
 
+pub(crate) use bytecode::Compiled;
+mod bytecode;
+
+pub(crate) use operators::equals;
+mod operators;
+
+/// variables declared in a function, a block or the global scope.
+///
+/// variables are looked up from the innermost scope outwards, stopping after
+/// the first one not bubbling: function scopes do not see their caller ones
+/// while block scopes see the enclosing ones.
 pub struct Scope {
     bubble_variables: bool,
     variables: HashMap<Name, Value>,
@@ -69,70 +432,503 @@ impl Scope {
 }
 
 pub use context::Context;
+pub(crate) use context::{FIELD_SIZE, ITEM_SIZE};
 mod context {
-    use std::{cell::RefMut, collections::HashMap};
+    use std::{collections::HashMap, rc::Rc};
 
-    use lorgn_lang::ast::{
-        Assignment, Block, Break, Condition, Expr, FnCall, FnDef, Invoke, Litteral, Loop, Name,
-        Path, Return,
+    use gc::Gc;
+    use lorgn_lang::{
+        ast::{
+            Assignment, BExpr, BinaryOp, BinaryOperator, Block, Break, Call, Condition,
+            Declaration, Expr, Field, FieldAssignment, FnCall, FnDef, Index, IndexAssignment,
+            Invoke, Item, Lambda, Litteral, Loop, Name, Node, NodeId, Parameter, Path, Return,
+            UnaryOp, ValueDef,
+        },
+        typing::{Primitive, Type},
     };
 
-    use crate::{Function, Module, Value};
+    use crate::{
+        Captures, Closure, ErrorKind, Frame, Function, Module, RuntimeError, StackTrace, Value,
+    };
+
+    use super::{
+        bytecode::{self, Chunk, Compiled},
+        grow_stack, operators, CancelToken, Engine, EvRes, Limits, Scope,
+    };
+
+    mod vm;
+
+    /// name standing for closures in errors.
+    const LAMBDA_NAME: &str = "<lambda>";
 
-    use super::{EvRes, Scope};
+    /// what is needed to run either a defined function or a closure.
+    struct Body<'f> {
+        name: &'f Name,
+        parameters: &'f [Parameter],
+        result: &'f Option<Type>,
+        expressions: &'f Block,
+        node: Option<NodeId>,
+        /// compiled body, when run by the virtual machine.
+        chunk: Option<&'f Chunk>,
+    }
 
     pub struct Context<'r> {
         modules: &'r HashMap<Name, Module>,
         scopes: Vec<Scope>,
+        current_module: Option<Name>,
+        fuel: Option<&'r mut u64>,
+        cancel_token: CancelToken,
+        limits: Limits,
+        call_depth: usize,
+        /// loops being run in the current function.
+        loops: usize,
+        /// functions being run, the innermost last.
+        frames: Vec<Frame>,
+        /// approximate number of bytes allocated so far.
+        heap: usize,
+        engine: Engine,
+        /// generation of the modules, for the functions resolved by the
+        /// virtual machine.
+        generation: u64,
     }
 
     impl<'r> Context<'r> {
         pub fn new(modules: &'r mut HashMap<Name, Module>) -> Self {
-            let scopes = vec![];
-            Self { modules, scopes }
+            // the global scope stays at the bottom of the stack.
+            let scopes = vec![Scope::new(false)];
+            Self {
+                modules,
+                scopes,
+                current_module: None,
+                fuel: None,
+                cancel_token: CancelToken::default(),
+                limits: Limits::default(),
+                call_depth: 0,
+                loops: 0,
+                frames: vec![],
+                heap: 0,
+                engine: Engine::default(),
+                generation: 0,
+            }
+        }
+
+        /// runs the scripts with the given engine, `generation` changing
+        /// whenever the modules do.
+        pub fn with_engine(mut self, engine: Engine, generation: u64) -> Self {
+            self.engine = engine;
+            self.generation = generation;
+            self
+        }
+
+        /// limits the evaluation to `fuel` expressions, decreasing it for each
+        /// one evaluated.
+        pub fn with_fuel(mut self, fuel: &'r mut u64) -> Self {
+            self.fuel = Some(fuel);
+            self
+        }
+
+        pub fn with_cancel_token(mut self, cancel_token: CancelToken) -> Self {
+            self.cancel_token = cancel_token;
+            self
+        }
+
+        pub fn with_globals(mut self, globals: Scope) -> Self {
+            self.scopes[0] = globals;
+            self
+        }
+
+        pub fn into_globals(self) -> Scope {
+            self.scopes.into_iter().next().unwrap_or(Scope::new(false))
+        }
+
+        pub fn with_limits(mut self, limits: Limits) -> Self {
+            self.limits = limits;
+            self
         }
 
-        pub fn find_function(&self, path: &Path) -> Option<RefMut<Function>> {
-            self.modules
+        /// accounts for the memory of a value newly created, failing when
+        /// it exceeds the heap limit.
+        pub fn allocate(&mut self, value: &Value) -> Result<(), RuntimeError> {
+            self.charge(heap_size(value))
+        }
+
+        /// accounts for `bytes` more of memory, as when a list or an object
+        /// grows.
+        pub fn charge(&mut self, bytes: usize) -> Result<(), RuntimeError> {
+            self.reserve(bytes)?;
+            self.heap += bytes;
+            Ok(())
+        }
+
+        /// fails when `bytes` more of memory would exceed the heap limit,
+        /// without accounting for them, for natives to check before building
+        /// a large value.
+        pub fn reserve(&self, bytes: usize) -> Result<(), RuntimeError> {
+            match self.limits.max_heap {
+                Some(max) if self.heap.saturating_add(bytes) > max => {
+                    Err(ErrorKind::HeapLimitExceeded(max).into())
+                }
+                _ => Ok(()),
+            }
+        }
+
+        /// functions being run, the innermost last.
+        pub fn frames(&self) -> &[Frame] {
+            &self.frames
+        }
+
+        /// runs `run` within a frame for a function of the current module.
+        pub(crate) fn in_frame<T>(
+            &mut self,
+            function: &Name,
+            run: impl FnOnce(&mut Self) -> Result<T, RuntimeError>,
+        ) -> Result<T, RuntimeError> {
+            self.frames.push(Frame {
+                module: self.current_module.clone(),
+                function: function.clone(),
+                node: None,
+            });
+            let result = run(self).map_err(|error| self.traced(error));
+            self.frames.pop();
+            result
+        }
+
+        /// node being run in the current frame.
+        fn set_node(&mut self, node: Option<NodeId>) {
+            if let (Some(frame), Some(_)) = (self.frames.last_mut(), node) {
+                frame.node = node;
+            }
+        }
+
+        /// attaches the active frames to an error unless it already has some.
+        fn traced(&self, mut error: RuntimeError) -> RuntimeError {
+            if error.trace.is_empty() {
+                error.trace = StackTrace(self.frames.iter().rev().cloned().collect());
+            }
+            error
+        }
+
+        /// module whose code is being evaluated, `None` for the host.
+        pub fn current_module(&self) -> Option<&Name> {
+            self.current_module.as_ref()
+        }
+
+        pub fn find_function(&self, path: &Path) -> Result<&'r Function, RuntimeError> {
+            let module = self
+                .modules
+                .get(&path.module)
+                .ok_or_else(|| ErrorKind::UnknownModule(path.module.clone()))?;
+            let function = module
+                .get_function(&path.item)
+                .ok_or_else(|| ErrorKind::UnknownFunction(path.clone()))?;
+            let is_local = self.current_module() == Some(&path.module);
+            if !is_local && !module.is_exported(&path.item) {
+                return Err(ErrorKind::PrivateFunction(path.clone()).into());
+            }
+            Ok(function)
+        }
+
+        /// value of a constant or global, those of other modules having to be
+        /// exported.
+        pub fn find_item(&self, path: &Path) -> Result<Value, RuntimeError> {
+            let module = self
+                .modules
                 .get(&path.module)
-                .and_then(|m| m.get_function(&path.item))
+                .ok_or_else(|| ErrorKind::UnknownModule(path.module.clone()))?;
+            let value = module
+                .get_item(&path.item)
+                .ok_or_else(|| ErrorKind::UnknownItem(path.clone()))?;
+            let is_local = self.current_module() == Some(&path.module);
+            if !is_local && !module.is_exported(&path.item) {
+                return Err(ErrorKind::PrivateItem(path.clone()).into());
+            }
+            Ok(value)
+        }
+
+        /// module of the code being evaluated, whose constants and globals
+        /// are visible by name.
+        fn local_module(&self) -> Option<&'r Module> {
+            self.modules.get(self.current_module.as_ref()?)
+        }
+
+        /// evaluates the constants and globals of a registered module in
+        /// order, each one seeing those before it.
+        pub(crate) fn initialize(
+            &mut self,
+            module: &Name,
+            initializers: &[(ValueDef, bool)],
+        ) -> Result<(), RuntimeError> {
+            let caller = self.current_module.replace(module.clone());
+            let result = initializers.iter().try_for_each(|(definition, mutable)| {
+                let value = self.in_frame(&definition.name, |context| {
+                    context.run_node(&definition.value)
+                })?;
+                if let Some(module) = self.local_module() {
+                    module.define_item(definition.name.clone(), value, *mutable);
+                }
+                Ok(())
+            });
+            self.current_module = caller;
+            result
+        }
+
+        pub fn call_function(
+            &mut self,
+            path: &Path,
+            args: Vec<Value>,
+        ) -> Result<Value, RuntimeError> {
+            let function = self.find_function(path)?;
+            self.call_in(&path.module, function, args)
+        }
+
+        /// calls a function with `module` as the current one.
+        pub fn call_in(
+            &mut self,
+            module: &Name,
+            function: &Function,
+            args: Vec<Value>,
+        ) -> Result<Value, RuntimeError> {
+            let caller = self.current_module.replace(module.clone());
+            let result = function.call(args, self);
+            self.current_module = caller;
+            result
         }
 
         pub fn find_variable(&mut self, name: &Name) -> Option<&mut Value> {
             for scope in self.scopes.iter_mut().rev() {
-                if !scope.bubble_variables {
-                    return None;
-                }
                 if let Some(result) = scope.variables.get_mut(name) {
                     return Some(result);
                 }
+                if !scope.bubble_variables {
+                    return None;
+                }
             }
             None
         }
 
-        pub fn run_fun(&mut self, fn_def: &FnDef, params: Vec<Value>) -> Value {
-            let variables = fn_def
-                .parameters
-                .iter()
-                .cloned()
-                .zip(params.into_iter())
-                .collect();
-            self.push_scope(Scope::new_with(variables, false));
-            let res = self.eval_block(&fn_def.expressions);
-            self.pop_scope();
-            match res {
-                EvRes::Value(res) => res,
-                EvRes::ReturnSC(res) => res,
-                EvRes::BreakSC(_) => panic!("break outside of loop"),
+        pub(crate) fn run_fun(
+            &mut self,
+            fn_def: &FnDef,
+            code: &Compiled,
+            params: Vec<Value>,
+        ) -> Result<Value, RuntimeError> {
+            let chunk = match self.engine {
+                Engine::TreeWalker => None,
+                Engine::Bytecode => {
+                    Some(code.get_or_compile(|| bytecode::compile_function(fn_def)))
+                }
+            };
+            let function = Body {
+                name: &fn_def.name,
+                parameters: &fn_def.parameters,
+                result: &fn_def.result,
+                expressions: &fn_def.expressions,
+                node: fn_def.meta.id,
+                chunk: chunk.map(|chunk| &**chunk),
+            };
+            grow_stack(|| self.run_body(function, Captures::default(), params))
+        }
+
+        /// calls a function value with the module it was created in as the
+        /// current one.
+        pub fn call_value(
+            &mut self,
+            function: &Value,
+            params: Vec<Value>,
+        ) -> Result<Value, RuntimeError> {
+            let Value::Function(closure) = function else {
+                return Err(ErrorKind::TypeMismatch {
+                    expected: "function",
+                    found: function.type_name(),
+                }
+                .into());
+            };
+            let lambda = &closure.lambda;
+            let chunk = match self.engine {
+                Engine::TreeWalker => None,
+                Engine::Bytecode => Some(
+                    closure
+                        .code
+                        .get_or_compile(|| bytecode::compile_lambda(lambda)),
+                ),
+            };
+            let function = Body {
+                name: &LAMBDA_NAME.into(),
+                parameters: &lambda.parameters,
+                result: &lambda.result,
+                expressions: &lambda.expressions,
+                node: None,
+                chunk: chunk.map(|chunk| &**chunk),
+            };
+            let caller = std::mem::replace(&mut self.current_module, closure.module.clone());
+            let result = grow_stack(|| self.run_body(function, closure.captures.clone(), params));
+            self.current_module = caller;
+            result
+        }
+
+        /// calls a method of a host object, given the object followed by the
+        /// arguments.
+        pub fn call_method(
+            &mut self,
+            target: &Value,
+            method: &Name,
+            mut args: Vec<Value>,
+        ) -> Result<Value, RuntimeError> {
+            let Value::UserData(user_data) = target else {
+                return Err(ErrorKind::TypeMismatch {
+                    expected: "userdata",
+                    found: target.type_name(),
+                }
+                .into());
+            };
+            let user_type = user_data.user_type().clone();
+            let Some(function) = user_type.method(method) else {
+                return Err(ErrorKind::UnknownMethod {
+                    type_name: user_type.name(),
+                    method: method.clone(),
+                }
+                .into());
+            };
+            args.insert(0, target.clone());
+            function.call(args, self)
+        }
+
+        fn run_body(
+            &mut self,
+            function: Body,
+            captures: Captures,
+            mut params: Vec<Value>,
+        ) -> Result<Value, RuntimeError> {
+            let Body {
+                name,
+                parameters,
+                result: expected_result,
+                expressions,
+                node,
+                chunk,
+            } = function;
+            // the errors of the call are traced with the frame of the callee,
+            // at the node of its definition.
+            self.in_frame(name, |context| {
+                context.set_node(node);
+                if parameters.len() != params.len() {
+                    return Err(ErrorKind::ArityMismatch {
+                        function: name.clone(),
+                        expected: parameters.len().into(),
+                        found: params.len(),
+                    }
+                    .into());
+                }
+                for (parameter, value) in parameters.iter().zip(&mut params) {
+                    let found = value.type_of();
+                    match &parameter.ty {
+                        Some(expected) if !expected.accepts(&found) => {
+                            return Err(ErrorKind::ParameterMismatch {
+                                function: name.clone(),
+                                parameter: parameter.name.clone(),
+                                expected: expected.clone(),
+                                found,
+                            }
+                            .into())
+                        }
+                        Some(expected) => promote(expected, value),
+                        None => (),
+                    }
+                }
+                if let Some(max) = context.limits.max_call_depth {
+                    if context.call_depth >= max {
+                        let error = RuntimeError::from(ErrorKind::CallDepthExceeded(max));
+                        return Err(error.at(node));
+                    }
+                }
+                // the virtual machine keeps the variables in slots, the scope
+                // only accounts for the limits.
+                let (scope, slots) = match chunk {
+                    Some(chunk) => (Scope::new(false), chunk.slots(params, &captures)),
+                    None => {
+                        let captures = captures.iter().map(|(n, v)| (n.clone(), v.clone()));
+                        let names = parameters.iter().map(|p| p.name.clone());
+                        let variables = captures.chain(names.zip(params)).collect();
+                        (Scope::new_with(variables, false), vec![])
+                    }
+                };
+                context.push_scope(scope).map_err(|error| error.at(node))?;
+                context.call_depth += 1;
+                // loops of the caller do not enclose the body.
+                let loops = std::mem::take(&mut context.loops);
+                let res = match chunk {
+                    Some(chunk) => context.run_chunk(chunk, slots).map(EvRes::ReturnSC),
+                    None => context.eval_block(expressions),
+                };
+                context.loops = loops;
+                context.call_depth -= 1;
+                context.pop_scope();
+                context.set_node(node);
+                let mut result = match res? {
+                    EvRes::Value(res) => res,
+                    EvRes::ReturnSC(res) => res,
+                    EvRes::BreakSC(_) => {
+                        let error = RuntimeError::from(ErrorKind::BreakOutsideLoop);
+                        return Err(error.at(node));
+                    }
+                };
+                let found = result.type_of();
+                match expected_result {
+                    Some(expected) if !expected.accepts(&found) => {
+                        let error = RuntimeError::from(ErrorKind::ResultMismatch {
+                            function: name.clone(),
+                            expected: expected.clone(),
+                            found,
+                        });
+                        Err(error.at(node))
+                    }
+                    Some(expected) => {
+                        promote(expected, &mut result);
+                        Ok(result)
+                    }
+                    None => Ok(result),
+                }
+            })
+        }
+
+        pub fn run_expr(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+            if self.engine == Engine::Bytecode {
+                let chunk = bytecode::compile_expression(expr, None);
+                let slots = vec![None; chunk.slot_count];
+                return self.run_chunk(&chunk, slots);
+            }
+            self.consume_fuel()?;
+            match self.eval_expr(expr)? {
+                EvRes::Value(res) => Ok(res),
+                EvRes::ReturnSC(res) => Ok(res),
+                EvRes::BreakSC(_) => Err(ErrorKind::BreakOutsideLoop.into()),
             }
         }
 
-        pub fn run_expr(&mut self, expr: &Expr) -> Value {
-            self.eval_expr(expr).into_value().unwrap()
+        fn run_node(&mut self, node: &Node) -> Result<Value, RuntimeError> {
+            if self.engine == Engine::Bytecode {
+                let chunk = bytecode::compile_expression(&node.expr, node.meta.id);
+                let slots = vec![None; chunk.slot_count];
+                return self.run_chunk(&chunk, slots);
+            }
+            match self.eval_node(node)? {
+                EvRes::Value(res) => Ok(res),
+                EvRes::ReturnSC(res) => Ok(res),
+                EvRes::BreakSC(_) => {
+                    let error = RuntimeError::from(ErrorKind::BreakOutsideLoop);
+                    Err(error.at(node.meta.id))
+                }
+            }
         }
 
-        fn push_scope(&mut self, scope: Scope) {
-            self.scopes.push(scope)
+        fn push_scope(&mut self, scope: Scope) -> Result<(), RuntimeError> {
+            if let Some(max) = self.limits.max_scopes {
+                if self.scopes.len() >= max {
+                    return Err(ErrorKind::ScopeLimitExceeded(max).into());
+                }
+            }
+            self.scopes.push(scope);
+            Ok(())
         }
 
         fn pop_scope(&mut self) {
@@ -147,13 +943,53 @@ mod context {
             self.scopes.len() - 1
         }
 
-        fn eval_expr(&mut self, expr: &Expr) -> EvRes {
+        fn eval_node(&mut self, node: &Node) -> Result<EvRes, RuntimeError> {
+            let outer = self.frames.last_mut().map(|frame| {
+                let outer = frame.node;
+                frame.node = node.meta.id.or(outer);
+                outer
+            });
+            let result = self
+                .consume_fuel()
+                .and_then(|_| grow_stack(|| self.eval_expr(&node.expr)))
+                .map_err(|error| self.traced(error).at(node.meta.id));
+            if let (Some(frame), Some(outer)) = (self.frames.last_mut(), outer) {
+                frame.node = outer;
+            }
+            result
+        }
+
+        /// stops the evaluation when cancelled or out of fuel.
+        fn consume_fuel(&mut self) -> Result<(), RuntimeError> {
+            if self.cancel_token.is_cancelled() {
+                return Err(ErrorKind::Cancelled.into());
+            }
+            if let Some(fuel) = &mut self.fuel {
+                if **fuel == 0 {
+                    return Err(ErrorKind::OutOfFuel.into());
+                }
+                **fuel -= 1;
+            }
+            Ok(())
+        }
+
+        fn eval_expr(&mut self, expr: &Expr) -> Result<EvRes, RuntimeError> {
             match expr {
                 Expr::Block(block) => self.eval_block(block),
                 Expr::Assignment(assignment) => self.eval_assignment(assignment),
+                Expr::Declaration(declaration) => self.eval_declaration(declaration),
                 Expr::Invoke(invoke) => self.eval_invoke(invoke),
+                Expr::Item(item) => self.eval_item(item),
+                Expr::Index(index) => self.eval_index(index),
+                Expr::IndexAssignment(assignment) => self.eval_index_assignment(assignment),
+                Expr::Field(field) => self.eval_field(field),
+                Expr::FieldAssignment(assignment) => self.eval_field_assignment(assignment),
                 Expr::Litteral(litteral) => self.eval_litteral(litteral),
                 Expr::FnCall(fn_call) => self.eval_fn_call(fn_call),
+                Expr::Lambda(lambda) => self.eval_lambda(lambda),
+                Expr::Call(call) => self.eval_call(call),
+                Expr::BinaryOp(binary_op) => self.eval_binary_op(binary_op),
+                Expr::UnaryOp(unary_op) => self.eval_unary_op(unary_op),
                 Expr::Condition(condition) => self.eval_condition(condition),
                 Expr::Loop(loop_) => self.eval_loop(loop_),
                 Expr::Return(return_) => self.eval_return(return_),
@@ -161,38 +997,140 @@ mod context {
             }
         }
 
-        fn eval_block(&mut self, block: &Block) -> EvRes {
+        /// evaluates the expressions of a block in a scope of their own.
+        fn eval_block(&mut self, block: &Block) -> Result<EvRes, RuntimeError> {
+            self.push_scope(Scope::new(true))?;
+            let result = self.eval_expressions(&block.expressions);
+            self.pop_scope();
+            result
+        }
+
+        fn eval_expressions(&mut self, expressions: &[BExpr]) -> Result<EvRes, RuntimeError> {
             let mut last = None;
-            for expr in &block.expressions {
-                let result = self.eval_expr(expr);
+            for expr in expressions {
+                let result = self.eval_node(expr)?;
                 if let EvRes::Value(result) = result {
                     last = Some(result);
                 } else {
-                    return result;
+                    return Ok(result);
                 }
             }
             let result = last.unwrap_or(Value::None);
-            EvRes::new_val(result)
+            Ok(EvRes::new_val(result))
+        }
+
+        /// updates the visible variable or else the global of the current
+        /// module, declaring a variable in the current scope when there is
+        /// neither.
+        fn eval_assignment(&mut self, assignment: &Assignment) -> Result<EvRes, RuntimeError> {
+            let result = self.eval_node(&assignment.value)?;
+            if let EvRes::Value(result) = result {
+                let name = &assignment.variable_name;
+                if let Some(variable) = self.find_variable(name) {
+                    *variable = result.clone();
+                } else if let Some(module) = self.local_module().filter(|m| m.has_item(name)) {
+                    module.set_item(name, result.clone())?;
+                } else {
+                    self.declare(name.clone(), result.clone());
+                }
+                Ok(EvRes::new_val(result))
+            } else {
+                Ok(result)
+            }
         }
 
-        fn eval_assignment(&mut self, assignment: &Assignment) -> EvRes {
-            let result = self.eval_expr(&assignment.value);
+        fn eval_declaration(&mut self, declaration: &Declaration) -> Result<EvRes, RuntimeError> {
+            let result = self.eval_node(&declaration.value)?;
             if let EvRes::Value(result) = result {
-                let name = assignment.variable_name.clone();
-                self.top_scope().unwrap().insert(name, result.clone());
-                EvRes::new_val(result)
+                self.declare(declaration.variable_name.clone(), result.clone());
+                Ok(EvRes::new_val(result))
             } else {
-                result
+                Ok(result)
             }
         }
 
-        fn eval_invoke(&mut self, invoke: &Invoke) -> EvRes {
-            let value = self.find_variable(&invoke.variable_name).unwrap().clone();
-            EvRes::new_val(value)
+        fn declare(&mut self, name: Name, value: Value) {
+            if let Some(scope) = self.top_scope() {
+                scope.insert(name, value);
+            }
+        }
+
+        fn eval_invoke(&mut self, invoke: &Invoke) -> Result<EvRes, RuntimeError> {
+            let name = &invoke.variable_name;
+            let value = match self.find_variable(name) {
+                Some(value) => value.clone(),
+                None => self
+                    .local_module()
+                    .and_then(|module| module.get_item(name))
+                    .ok_or_else(|| ErrorKind::UndefinedVariable(name.clone()))?,
+            };
+            Ok(EvRes::new_val(value))
         }
 
-        fn eval_litteral(&mut self, litteral: &Litteral) -> EvRes {
-            match litteral {
+        fn eval_item(&mut self, item: &Item) -> Result<EvRes, RuntimeError> {
+            Ok(EvRes::new_val(self.find_item(&item.item_path)?))
+        }
+
+        fn eval_index(&mut self, index: &Index) -> Result<EvRes, RuntimeError> {
+            let target = match self.eval_node(&index.target)? {
+                EvRes::Value(target) => target,
+                short_circuit => return Ok(short_circuit),
+            };
+            let key = match self.eval_node(&index.index)? {
+                EvRes::Value(key) => key,
+                short_circuit => return Ok(short_circuit),
+            };
+            Ok(EvRes::new_val(get_index(&target, &key)?))
+        }
+
+        fn eval_index_assignment(
+            &mut self,
+            assignment: &IndexAssignment,
+        ) -> Result<EvRes, RuntimeError> {
+            let target = match self.eval_node(&assignment.target)? {
+                EvRes::Value(target) => target,
+                short_circuit => return Ok(short_circuit),
+            };
+            let key = match self.eval_node(&assignment.index)? {
+                EvRes::Value(key) => key,
+                short_circuit => return Ok(short_circuit),
+            };
+            let value = match self.eval_node(&assignment.value)? {
+                EvRes::Value(value) => value,
+                short_circuit => return Ok(short_circuit),
+            };
+            let grown = set_index(&target, &key, value.clone())?;
+            self.charge(grown)?;
+            Ok(EvRes::new_val(value))
+        }
+
+        fn eval_field(&mut self, field: &Field) -> Result<EvRes, RuntimeError> {
+            let target = match self.eval_node(&field.target)? {
+                EvRes::Value(target) => target,
+                short_circuit => return Ok(short_circuit),
+            };
+            Ok(EvRes::new_val(get_field(&target, &field.field)?))
+        }
+
+        fn eval_field_assignment(
+            &mut self,
+            assignment: &FieldAssignment,
+        ) -> Result<EvRes, RuntimeError> {
+            let target = match self.eval_node(&assignment.target)? {
+                EvRes::Value(target) => target,
+                short_circuit => return Ok(short_circuit),
+            };
+            let value = match self.eval_node(&assignment.value)? {
+                EvRes::Value(value) => value,
+                short_circuit => return Ok(short_circuit),
+            };
+            let grown = set_field(&target, assignment.field.clone(), value.clone())?;
+            self.charge(grown)?;
+            Ok(EvRes::new_val(value))
+        }
+
+        fn eval_litteral(&mut self, litteral: &Litteral) -> Result<EvRes, RuntimeError> {
+            let result = match litteral {
                 Litteral::String(str) => EvRes::Value(str.clone().into()),
                 Litteral::Integer(int) => EvRes::Value((*int).into()),
                 Litteral::Float(flt) => EvRes::Value((*flt).into()),
@@ -200,11 +1138,11 @@ mod context {
                 Litteral::List(vec) => {
                     let mut results = vec![];
                     for expr in vec {
-                        let result = self.eval_expr(expr);
+                        let result = self.eval_node(expr)?;
                         if let EvRes::Value(result) = result {
                             results.push(result);
                         } else {
-                            return result;
+                            return Ok(result);
                         }
                     }
                     EvRes::Value(results.into())
@@ -212,76 +1150,290 @@ mod context {
                 Litteral::Map(map) => {
                     let mut results = HashMap::new();
                     for (name, expr) in map {
-                        let result = self.eval_expr(expr);
+                        let result = self.eval_node(expr)?;
                         if let EvRes::Value(result) = result {
                             results.insert(name.clone(), result);
                         } else {
-                            return result;
+                            return Ok(result);
                         }
                     }
                     EvRes::Value(results.into())
                 }
+            };
+            if let EvRes::Value(value) = &result {
+                self.allocate(value)?;
             }
+            Ok(result)
         }
 
-        fn eval_fn_call(&mut self, fn_call: &FnCall) -> EvRes {
+        fn eval_fn_call(&mut self, fn_call: &FnCall) -> Result<EvRes, RuntimeError> {
             let mut args = vec![];
             for arg in &fn_call.arguments {
-                let res = self.eval_expr(arg);
+                let res = self.eval_node(arg)?;
+                if res.is_short_circuit() {
+                    return Ok(res);
+                }
+                args.push(res.into_value().unwrap());
+            }
+            let res = self.call_function(&fn_call.fn_path, args)?;
+            Ok(EvRes::Value(res))
+        }
+
+        fn eval_lambda(&mut self, lambda: &Lambda) -> Result<EvRes, RuntimeError> {
+            let mut captures = Captures::default();
+            for scope in self.scopes.iter().rev() {
+                for (name, value) in &scope.variables {
+                    if !captures.contains(name) {
+                        captures.insert(name.clone(), value.clone());
+                    }
+                }
+                if !scope.bubble_variables {
+                    break;
+                }
+            }
+            let closure = Closure::new(
+                Rc::new(lambda.clone()),
+                self.current_module.clone(),
+                captures,
+            );
+            Ok(EvRes::new_val(Value::Function(Gc::new(closure))))
+        }
+
+        fn eval_call(&mut self, call: &Call) -> Result<EvRes, RuntimeError> {
+            let (function, method) = match &call.function.expr {
+                // the target of a field is kept to call methods of host objects.
+                Expr::Field(field) => {
+                    let target = match self.eval_node(&field.target)? {
+                        EvRes::Value(target) => target,
+                        short_circuit => return Ok(short_circuit),
+                    };
+                    if matches!(target, Value::UserData(_)) {
+                        (target, Some(&field.field))
+                    } else {
+                        let function = get_field(&target, &field.field)
+                            .map_err(|error| error.at(call.function.meta.id))?;
+                        (function, None)
+                    }
+                }
+                _ => match self.eval_node(&call.function)? {
+                    EvRes::Value(function) => (function, None),
+                    short_circuit => return Ok(short_circuit),
+                },
+            };
+            let mut args = vec![];
+            for arg in &call.arguments {
+                let res = self.eval_node(arg)?;
                 if res.is_short_circuit() {
-                    return res;
+                    return Ok(res);
                 }
                 args.push(res.into_value().unwrap());
             }
-            let path = &fn_call.fn_path;
-            let module = self.modules.get(&path.module).unwrap();
-            let mut function = module.get_function(&path.item).unwrap();
-            let res = function.call(args, self);
-            EvRes::Value(res)
+            let res = match method {
+                Some(method) => self.call_method(&function, method, args)?,
+                None => self.call_value(&function, args)?,
+            };
+            Ok(EvRes::Value(res))
         }
 
-        fn eval_condition(&mut self, condition: &Condition) -> EvRes {
-            let cond = self.eval_expr(&condition.condition);
-            if cond.is_short_circuit() {
-                return cond;
+        fn eval_binary_op(&mut self, binary_op: &BinaryOp) -> Result<EvRes, RuntimeError> {
+            let left = match self.eval_node(&binary_op.left)? {
+                EvRes::Value(left) => left,
+                short_circuit => return Ok(short_circuit),
+            };
+            let operator = binary_op.operator;
+            if let BinaryOperator::And | BinaryOperator::Or = operator {
+                let left = expect_bool(left)?;
+                if left == (operator == BinaryOperator::Or) {
+                    return Ok(EvRes::new_val(left.into()));
+                }
+                return match self.eval_node(&binary_op.right)? {
+                    EvRes::Value(right) => Ok(EvRes::new_val(expect_bool(right)?.into())),
+                    short_circuit => Ok(short_circuit),
+                };
             }
-            let cond = cond.into_value().unwrap();
-            if cond.into_bool().unwrap() {
-                self.eval_expr(&condition.true_case)
+            let right = match self.eval_node(&binary_op.right)? {
+                EvRes::Value(right) => right,
+                short_circuit => return Ok(short_circuit),
+            };
+            let result = operators::binary(operator, left, right)?;
+            self.allocate(&result)?;
+            Ok(EvRes::new_val(result))
+        }
+
+        fn eval_unary_op(&mut self, unary_op: &UnaryOp) -> Result<EvRes, RuntimeError> {
+            let operand = match self.eval_node(&unary_op.operand)? {
+                EvRes::Value(operand) => operand,
+                short_circuit => return Ok(short_circuit),
+            };
+            let result = operators::unary(unary_op.operator, operand)?;
+            Ok(EvRes::new_val(result))
+        }
+
+        fn eval_condition(&mut self, condition: &Condition) -> Result<EvRes, RuntimeError> {
+            let cond = self.eval_node(&condition.condition)?;
+            let cond = match cond {
+                EvRes::Value(Value::Bool(cond)) => cond,
+                EvRes::Value(other) => {
+                    return Err(ErrorKind::TypeMismatch {
+                        expected: "bool",
+                        found: other.type_name(),
+                    }
+                    .into())
+                }
+                short_circuit => return Ok(short_circuit),
+            };
+            if cond {
+                self.eval_node(&condition.true_case)
             } else {
-                self.eval_expr(&condition.false_case)
+                self.eval_node(&condition.false_case)
             }
         }
 
-        fn eval_loop(&mut self, loop_: &Loop) -> EvRes {
+        fn eval_loop(&mut self, loop_: &Loop) -> Result<EvRes, RuntimeError> {
             let body = &loop_.body;
+            self.loops += 1;
             let result = loop {
-                let res = self.eval_expr(body);
-                match res {
-                    EvRes::ReturnSC(_) => return res,
-                    EvRes::BreakSC(result) => break result,
-                    _ => (),
+                match self.eval_node(body) {
+                    Ok(EvRes::BreakSC(result)) => break Ok(EvRes::new_val(result)),
+                    Ok(EvRes::Value(_)) => (),
+                    res => break res,
                 };
             };
-            EvRes::new_val(result)
+            self.loops -= 1;
+            result
         }
 
-        fn eval_return(&mut self, return_: &Return) -> EvRes {
-            let result = self.eval_expr(&return_.expression);
+        fn eval_return(&mut self, return_: &Return) -> Result<EvRes, RuntimeError> {
+            let result = self.eval_node(&return_.expression)?;
             match result {
-                EvRes::ReturnSC(_) => result,
-                EvRes::Value(v) => EvRes::ReturnSC(v),
-                EvRes::BreakSC(_) => panic!("break outside of loop"),
+                EvRes::Value(v) => Ok(EvRes::ReturnSC(v)),
+                short_circuit => Ok(short_circuit),
             }
         }
 
-        fn eval_break(&mut self, break_: &Break) -> EvRes {
-            let result = self.eval_expr(&break_.expression);
+        fn eval_break(&mut self, break_: &Break) -> Result<EvRes, RuntimeError> {
+            let result = self.eval_node(&break_.expression)?;
             match result {
-                EvRes::ReturnSC(_) => result,
-                EvRes::Value(v) => EvRes::BreakSC(v),
-                EvRes::BreakSC(v) => EvRes::BreakSC(v),
+                EvRes::Value(_) if self.loops == 0 => Err(ErrorKind::BreakOutsideLoop.into()),
+                EvRes::Value(v) => Ok(EvRes::BreakSC(v)),
+                short_circuit => Ok(short_circuit),
+            }
+        }
+    }
+    /// converts an integer given where a float is expected, as the
+    /// annotation accepts it.
+    fn promote(expected: &Type, value: &mut Value) {
+        if let (Type::Primitive(Primitive::Float), Value::Integer(int)) = (expected, &*value) {
+            *value = Value::Float(*int as f32);
+        }
+    }
+
+    fn expect_bool(value: Value) -> Result<bool, RuntimeError> {
+        match value {
+            Value::Bool(bool) => Ok(bool),
+            other => Err(ErrorKind::TypeMismatch {
+                expected: "bool",
+                found: other.type_name(),
             }
+            .into()),
+        }
+    }
+
+    /// position of `index` in `list`, when it is in range.
+    fn slot(list: &[Value], index: i32) -> Result<usize, RuntimeError> {
+        let length = list.len();
+        usize::try_from(index)
+            .ok()
+            .filter(|slot| *slot < length)
+            .ok_or_else(|| ErrorKind::IndexOutOfRange { index, length }.into())
+    }
+
+    fn get_index(target: &Value, index: &Value) -> Result<Value, RuntimeError> {
+        match (target, index) {
+            (Value::List(list), Value::Integer(index)) => {
+                let list = list.borrow();
+                Ok(list[slot(&list, *index)?].clone())
+            }
+            (Value::Object(_), Value::String(key)) => get_field(target, &key.clone().into()),
+            _ => Err(index_mismatch(target, index)),
+        }
+    }
+
+    /// sets an item of a list or a field of an object, returning the number
+    /// of bytes the target grew by.
+    fn set_index(target: &Value, index: &Value, value: Value) -> Result<usize, RuntimeError> {
+        match (target, index) {
+            (Value::List(list), Value::Integer(index)) => {
+                let mut list = list.borrow_mut();
+                let slot = slot(&list, *index)?;
+                list[slot] = value;
+                Ok(0)
+            }
+            (Value::Object(_), Value::String(key)) => set_field(target, key.clone().into(), value),
+            _ => Err(index_mismatch(target, index)),
+        }
+    }
+
+    fn index_mismatch(target: &Value, index: &Value) -> RuntimeError {
+        let (expected, found) = match target {
+            Value::List(_) => ("integer", index.type_name()),
+            Value::Object(_) => ("string", index.type_name()),
+            other => ("list or object", other.type_name()),
+        };
+        ErrorKind::TypeMismatch { expected, found }.into()
+    }
+
+    fn get_field(target: &Value, field: &Name) -> Result<Value, RuntimeError> {
+        match target {
+            Value::Object(object) => object
+                .borrow()
+                .get(field)
+                .cloned()
+                .ok_or_else(|| ErrorKind::MissingField(field.clone()).into()),
+            other => Err(object_mismatch(other)),
+        }
+    }
+
+    /// sets a field of an object, returning the number of bytes the object
+    /// grew by.
+    fn set_field(target: &Value, field: Name, value: Value) -> Result<usize, RuntimeError> {
+        match target {
+            Value::Object(object) => {
+                let mut object = object.borrow_mut();
+                let grown = if object.contains(&field) {
+                    0
+                } else {
+                    FIELD_SIZE
+                };
+                object.insert(field, value);
+                Ok(grown)
+            }
+            other => Err(object_mismatch(other)),
+        }
+    }
+
+    /// approximate memory owned by a value itself, not counting the values
+    /// it holds.
+    fn heap_size(value: &Value) -> usize {
+        match value {
+            Value::String(str) => str.len(),
+            Value::List(list) => list.borrow().len() * ITEM_SIZE,
+            Value::Object(object) => object.borrow().fields().len() * FIELD_SIZE,
+            _ => 0,
+        }
+    }
+
+    /// approximate memory of an item of a list.
+    pub(crate) const ITEM_SIZE: usize = std::mem::size_of::<Value>();
+    /// approximate memory of a field of an object.
+    pub(crate) const FIELD_SIZE: usize = std::mem::size_of::<Name>() + ITEM_SIZE;
+
+    fn object_mismatch(found: &Value) -> RuntimeError {
+        let found = found.type_name();
+        ErrorKind::TypeMismatch {
+            expected: "object",
+            found,
         }
+        .into()
     }
 }
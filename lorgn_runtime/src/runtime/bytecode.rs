@@ -0,0 +1,599 @@
+use std::{cell::OnceCell, cell::RefCell, collections::HashMap, fmt::Debug, rc::Rc};
+
+use lorgn_lang::ast::{
+    BinaryOperator, Block, Expr, FnDef, Lambda, Litteral, Name, Node, NodeId, Parameter, Path,
+    UnaryOperator,
+};
+
+use super::grow_stack;
+use crate::{Captures, FunctionHandle, Value};
+
+/// instruction of the virtual machine, working on a stack of values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Constant(u32),
+    PushNone,
+    Pop,
+    /// pushes the value of a variable.
+    Load(u32),
+    /// assigns the value on top of the stack to a variable, keeping it.
+    Assign(u32),
+    /// declares a variable with the value on top of the stack, keeping it.
+    Declare(Place),
+    /// pushes a constant or global of a module.
+    Item(u32),
+    Index,
+    SetIndex,
+    Field(u32),
+    SetField(u32),
+    /// gathers values into a list.
+    List(u32),
+    /// gathers values into an object, with the keys of the given index.
+    Map(u32),
+    /// calls a function from a call site with a number of arguments.
+    Call(u32, u32),
+    /// calls a function value followed by a number of arguments.
+    CallValue(u32),
+    /// replaces an object by one of its fields, or keeps a host object,
+    /// followed by whether a method is to be called.
+    PrepareMethod(u32),
+    CallMethod(u32, u32),
+    /// creates a closure from a prototype.
+    Closure(u32),
+    Binary(BinaryOperator),
+    Unary(UnaryOperator),
+    /// pops a bool, jumping when it is false.
+    JumpIfFalse(u32),
+    /// pops a bool, pushing it back and jumping when it is the given one.
+    JumpIf(bool, u32),
+    ExpectBool,
+    Jump(u32),
+    /// clears the slots of a block and enters its scope.
+    EnterBlock(u32, u32),
+    ExitBlock,
+    /// leaves a loop, keeping the value on top of the stack along with the
+    /// given number of values and blocks.
+    Unwind(u32, u32),
+    Return,
+    BreakOutsideLoop,
+    /// starts an expression, consuming fuel as the tree walker does for
+    /// each node it evaluates.
+    Fuel,
+}
+
+/// where a variable is declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Place {
+    Slot(u32),
+    /// variable of the global scope, with the index of its name.
+    Global(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    String(String),
+    Integer(i32),
+    Float(f32),
+    Bool(bool),
+}
+
+impl Constant {
+    pub fn value(&self) -> Value {
+        match self {
+            Self::String(str) => str.clone().into(),
+            Self::Integer(int) => (*int).into(),
+            Self::Float(flt) => (*flt).into(),
+            Self::Bool(bool) => (*bool).into(),
+        }
+    }
+}
+
+/// variable as resolved at compilation time.
+///
+/// a name may be declared in several enclosing blocks, or only on some
+/// paths, so its value is taken from the first slot holding one, the global
+/// scope and the constants and globals of the module coming after.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Variable {
+    pub name: Name,
+    /// slots the name may be declared in, the innermost first.
+    pub slots: Vec<u32>,
+    /// whether the global scope is visible after the slots.
+    pub global: bool,
+    /// where an assignment declares the variable when it is found nowhere.
+    pub place: Option<Place>,
+}
+
+/// function called from a call site, resolved on its first call.
+#[derive(Debug)]
+pub struct CallSite {
+    pub path: Path,
+    /// resolved function along with the generation of the modules it was
+    /// resolved in, registering a module making it stale.
+    pub cache: RefCell<Option<(u64, FunctionHandle)>>,
+}
+
+/// lambda along with its compiled body.
+#[derive(Debug)]
+pub struct Prototype {
+    pub lambda: Rc<Lambda>,
+    pub chunk: Rc<Chunk>,
+    /// variables of the enclosing code captured by name.
+    pub captures: Vec<u32>,
+}
+
+/// compiled function body or top-level expression.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub ops: Vec<Op>,
+    /// node each instruction was compiled from.
+    pub nodes: Vec<Option<NodeId>>,
+    pub constants: Vec<Constant>,
+    pub names: Vec<Name>,
+    pub paths: Vec<Path>,
+    pub keys: Vec<Vec<Name>>,
+    pub sites: Vec<CallSite>,
+    pub variables: Vec<Variable>,
+    pub prototypes: Vec<Prototype>,
+    pub slot_count: usize,
+    /// number of leading slots holding the parameters.
+    pub parameter_count: usize,
+    /// names captured by a lambda, along with their slots.
+    pub captured: Vec<(Name, u32)>,
+}
+
+impl Chunk {
+    /// initial slots of a call, the missing captures being left empty.
+    pub fn slots(&self, params: Vec<Value>, captures: &Captures) -> Vec<Option<Value>> {
+        let mut slots = vec![None; self.slot_count];
+        for (slot, value) in slots.iter_mut().zip(params) {
+            *slot = Some(value);
+        }
+        for (name, slot) in &self.captured {
+            slots[*slot as usize] = captures.get(name).cloned();
+        }
+        slots
+    }
+}
+
+/// compiled code of a function, produced when it is first run by the
+/// virtual machine.
+#[derive(Default)]
+pub struct Compiled(OnceCell<Rc<Chunk>>);
+
+impl Compiled {
+    pub fn new(chunk: Rc<Chunk>) -> Self {
+        Self(OnceCell::from(chunk))
+    }
+
+    pub fn get_or_compile(&self, compile: impl FnOnce() -> Chunk) -> &Rc<Chunk> {
+        self.0.get_or_init(|| Rc::new(compile()))
+    }
+}
+
+impl Debug for Compiled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<Compiled>")
+    }
+}
+
+/// the code only derives from the function, it does not tell them apart.
+impl PartialEq for Compiled {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+pub fn compile_function(fn_def: &FnDef) -> Chunk {
+    Compiler::function(&fn_def.parameters, &[]).finish_body(&fn_def.expressions)
+}
+
+/// compiles a lambda, the names it uses besides its parameters being taken
+/// from its captures.
+pub fn compile_lambda(lambda: &Lambda) -> Chunk {
+    let captured = free_names(lambda);
+    Compiler::function(&lambda.parameters, &captured).finish_body(&lambda.expressions)
+}
+
+/// compiles an expression evaluated in the global scope.
+pub fn compile_expression(expr: &Expr, node: Option<NodeId>) -> Chunk {
+    let mut compiler = Compiler {
+        node,
+        ..Compiler::default()
+    };
+    compiler.emit(Op::Fuel);
+    compiler.expr(expr);
+    compiler.emit(Op::Return);
+    compiler.chunk
+}
+
+/// names a lambda may read from the variables it captures.
+fn free_names(lambda: &Lambda) -> Vec<Name> {
+    fn visit(node: &Node, names: &mut Vec<Name>) {
+        let name = match &node.expr {
+            Expr::Invoke(invoke) => Some(&invoke.variable_name),
+            Expr::Assignment(assignment) => Some(&assignment.variable_name),
+            _ => None,
+        };
+        if let Some(name) = name.filter(|name| !names.contains(name)) {
+            names.push(name.clone());
+        }
+        for child in node.expr.children() {
+            visit(child, names);
+        }
+    }
+
+    let mut names = vec![];
+    for expr in &lambda.expressions.expressions {
+        visit(expr, &mut names);
+    }
+    names.retain(|name| lambda.parameters.iter().all(|p| &p.name != name));
+    names
+}
+
+/// names declared in a function or a block, with their slots.
+struct Scope {
+    slots: HashMap<Name, u32>,
+    bubble_variables: bool,
+}
+
+struct Loop {
+    /// height of the stack when the loop starts.
+    height: u32,
+    /// number of blocks entered when the loop starts.
+    blocks: u32,
+    /// jumps to patch with the end of the loop.
+    breaks: Vec<usize>,
+}
+
+#[derive(Default)]
+struct Compiler {
+    chunk: Chunk,
+    /// scopes being compiled, the global one not being among them.
+    scopes: Vec<Scope>,
+    /// number of blocks entered.
+    blocks: u32,
+    /// height of the stack, in values.
+    height: u32,
+    loops: Vec<Loop>,
+    node: Option<NodeId>,
+    /// indices of the names and variables already in the chunk.
+    names: HashMap<Name, u32>,
+    variables: HashMap<Variable, u32>,
+}
+
+impl Compiler {
+    /// compiler for a function body, its parameters and captures being
+    /// the first slots.
+    fn function(parameters: &[Parameter], captured: &[Name]) -> Self {
+        let mut compiler = Self::default();
+        compiler.scopes.push(Scope {
+            slots: HashMap::new(),
+            bubble_variables: false,
+        });
+        for parameter in parameters {
+            compiler.declare(&parameter.name);
+        }
+        compiler.chunk.parameter_count = parameters.len();
+        for name in captured {
+            let Place::Slot(slot) = compiler.declare(name) else {
+                unreachable!("functions have a scope of their own");
+            };
+            compiler.chunk.captured.push((name.clone(), slot));
+        }
+        compiler
+    }
+
+    fn finish_body(mut self, body: &Block) -> Chunk {
+        self.block(body);
+        self.emit(Op::Return);
+        self.chunk
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        self.height = match op {
+            Op::Constant(_)
+            | Op::PushNone
+            | Op::Load(_)
+            | Op::Item(_)
+            | Op::Closure(_)
+            | Op::PrepareMethod(_) => self.height + 1,
+            Op::Pop
+            | Op::Index
+            | Op::SetField(_)
+            | Op::Binary(_)
+            | Op::JumpIfFalse(_)
+            | Op::JumpIf(..) => self.height - 1,
+            Op::SetIndex => self.height - 2,
+            Op::List(count) => self.height + 1 - count,
+            Op::Map(keys) => self.height + 1 - self.chunk.keys[keys as usize].len() as u32,
+            Op::Call(_, count) => self.height + 1 - count,
+            Op::CallValue(count) => self.height - count,
+            Op::CallMethod(_, count) => self.height - 1 - count,
+            _ => self.height,
+        };
+        self.chunk.ops.push(op);
+        self.chunk.nodes.push(self.node);
+        self.chunk.ops.len() - 1
+    }
+
+    fn here(&self) -> u32 {
+        self.chunk.ops.len() as u32
+    }
+
+    /// points a jump emitted at `index` to the next instruction.
+    fn patch(&mut self, index: usize) {
+        let target = self.here();
+        match &mut self.chunk.ops[index] {
+            Op::Jump(to) | Op::JumpIfFalse(to) | Op::JumpIf(_, to) => *to = target,
+            Op::EnterBlock(_, end) => *end = self.chunk.slot_count as u32,
+            op => unreachable!("{op:?} is not a jump"),
+        }
+    }
+
+    fn name(&mut self, name: &Name) -> u32 {
+        let names = &mut self.chunk.names;
+        *self.names.entry(name.clone()).or_insert_with(|| {
+            names.push(name.clone());
+            names.len() as u32 - 1
+        })
+    }
+
+    fn constant(&mut self, constant: Constant) {
+        let index = self.chunk.constants.len() as u32;
+        self.chunk.constants.push(constant);
+        self.emit(Op::Constant(index));
+    }
+
+    /// declares a name in the innermost scope, the global one when there is
+    /// none.
+    fn declare(&mut self, name: &Name) -> Place {
+        let Some(scope) = self.scopes.last_mut() else {
+            return Place::Global(self.name(name));
+        };
+        if let Some(slot) = scope.slots.get(name) {
+            return Place::Slot(*slot);
+        }
+        let slot = self.chunk.slot_count as u32;
+        self.chunk.slot_count += 1;
+        scope.slots.insert(name.clone(), slot);
+        Place::Slot(slot)
+    }
+
+    /// resolves a name against the scopes visible from the current one.
+    fn variable(&mut self, name: &Name, place: Option<Place>) -> u32 {
+        let mut slots = vec![];
+        let mut global = true;
+        for scope in self.scopes.iter().rev() {
+            slots.extend(scope.slots.get(name));
+            if !scope.bubble_variables {
+                global = false;
+                break;
+            }
+        }
+        let variable = Variable {
+            name: name.clone(),
+            slots,
+            global,
+            place,
+        };
+        let variables = &mut self.chunk.variables;
+        *self.variables.entry(variable.clone()).or_insert_with(|| {
+            variables.push(variable);
+            variables.len() as u32 - 1
+        })
+    }
+
+    fn node(&mut self, node: &Node) {
+        let outer = self.node;
+        self.node = node.meta.id.or(outer);
+        self.emit(Op::Fuel);
+        grow_stack(|| self.expr(&node.expr));
+        self.node = outer;
+    }
+
+    fn nodes<'n>(&mut self, nodes: impl IntoIterator<Item = &'n Box<Node>>) -> u32 {
+        let mut count = 0;
+        for node in nodes {
+            self.node(node);
+            count += 1;
+        }
+        count
+    }
+
+    fn block(&mut self, block: &Block) {
+        let enter = self.emit(Op::EnterBlock(self.chunk.slot_count as u32, 0));
+        self.scopes.push(Scope {
+            slots: HashMap::new(),
+            bubble_variables: true,
+        });
+        self.blocks += 1;
+        for (index, expr) in block.expressions.iter().enumerate() {
+            if index > 0 {
+                self.emit(Op::Pop);
+            }
+            self.node(expr);
+        }
+        if block.expressions.is_empty() {
+            self.emit(Op::PushNone);
+        }
+        self.blocks -= 1;
+        self.scopes.pop();
+        self.patch(enter);
+        self.emit(Op::ExitBlock);
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Block(block) => self.block(block),
+            Expr::Assignment(assignment) => {
+                self.node(&assignment.value);
+                let place = self.declare(&assignment.variable_name);
+                let variable = self.variable(&assignment.variable_name, Some(place));
+                self.emit(Op::Assign(variable));
+            }
+            Expr::Declaration(declaration) => {
+                self.node(&declaration.value);
+                let place = self.declare(&declaration.variable_name);
+                self.emit(Op::Declare(place));
+            }
+            Expr::Invoke(invoke) => {
+                let variable = self.variable(&invoke.variable_name, None);
+                self.emit(Op::Load(variable));
+            }
+            Expr::Item(item) => {
+                self.chunk.paths.push(item.item_path.clone());
+                self.emit(Op::Item(self.chunk.paths.len() as u32 - 1));
+            }
+            Expr::Index(index) => {
+                self.node(&index.target);
+                self.node(&index.index);
+                self.emit(Op::Index);
+            }
+            Expr::IndexAssignment(assignment) => {
+                self.node(&assignment.target);
+                self.node(&assignment.index);
+                self.node(&assignment.value);
+                self.emit(Op::SetIndex);
+            }
+            Expr::Field(field) => {
+                self.node(&field.target);
+                let name = self.name(&field.field);
+                self.emit(Op::Field(name));
+            }
+            Expr::FieldAssignment(assignment) => {
+                self.node(&assignment.target);
+                self.node(&assignment.value);
+                let name = self.name(&assignment.field);
+                self.emit(Op::SetField(name));
+            }
+            Expr::Litteral(litteral) => self.litteral(litteral),
+            Expr::FnCall(fn_call) => {
+                let count = self.nodes(&fn_call.arguments);
+                self.chunk.sites.push(CallSite {
+                    path: fn_call.fn_path.clone(),
+                    cache: RefCell::new(None),
+                });
+                let site = self.chunk.sites.len() as u32 - 1;
+                self.emit(Op::Call(site, count));
+            }
+            Expr::Lambda(lambda) => self.lambda(lambda),
+            Expr::Call(call) => match &call.function.expr {
+                // the target of a field is kept to call methods of host objects.
+                Expr::Field(field) => {
+                    let outer = self.node;
+                    self.node = call.function.meta.id.or(outer);
+                    self.node(&field.target);
+                    let name = self.name(&field.field);
+                    self.emit(Op::PrepareMethod(name));
+                    self.node = outer;
+                    let count = self.nodes(&call.arguments);
+                    self.emit(Op::CallMethod(name, count));
+                }
+                _ => {
+                    self.node(&call.function);
+                    let count = self.nodes(&call.arguments);
+                    self.emit(Op::CallValue(count));
+                }
+            },
+            Expr::BinaryOp(binary_op) => {
+                self.node(&binary_op.left);
+                let operator = binary_op.operator;
+                let decisive = match operator {
+                    BinaryOperator::And => false,
+                    BinaryOperator::Or => true,
+                    _ => {
+                        self.node(&binary_op.right);
+                        self.emit(Op::Binary(operator));
+                        return;
+                    }
+                };
+                let jump = self.emit(Op::JumpIf(decisive, 0));
+                self.node(&binary_op.right);
+                self.emit(Op::ExpectBool);
+                self.patch(jump);
+            }
+            Expr::UnaryOp(unary_op) => {
+                self.node(&unary_op.operand);
+                self.emit(Op::Unary(unary_op.operator));
+            }
+            Expr::Condition(condition) => {
+                self.node(&condition.condition);
+                let to_false = self.emit(Op::JumpIfFalse(0));
+                self.node(&condition.true_case);
+                let to_end = self.emit(Op::Jump(0));
+                self.height -= 1;
+                self.patch(to_false);
+                self.node(&condition.false_case);
+                self.patch(to_end);
+            }
+            Expr::Loop(loop_) => {
+                let start = self.here();
+                self.loops.push(Loop {
+                    height: self.height,
+                    blocks: self.blocks,
+                    breaks: vec![],
+                });
+                self.node(&loop_.body);
+                self.emit(Op::Pop);
+                self.emit(Op::Jump(start));
+                let loop_ = self.loops.pop().unwrap();
+                for jump in loop_.breaks {
+                    self.patch(jump);
+                }
+                self.height = loop_.height + 1;
+            }
+            Expr::Return(return_) => {
+                self.node(&return_.expression);
+                self.emit(Op::Return);
+            }
+            Expr::Break(break_) => {
+                self.node(&break_.expression);
+                let Some(loop_) = self.loops.last() else {
+                    self.emit(Op::BreakOutsideLoop);
+                    return;
+                };
+                let (height, blocks) = (loop_.height, loop_.blocks);
+                self.emit(Op::Unwind(height, blocks));
+                // the code following is never run, it is compiled as if the
+                // break gave a value.
+                let jump = self.emit(Op::Jump(0));
+                self.loops.last_mut().unwrap().breaks.push(jump);
+            }
+        }
+    }
+
+    fn litteral(&mut self, litteral: &Litteral) {
+        match litteral {
+            Litteral::String(str) => self.constant(Constant::String(str.clone())),
+            Litteral::Integer(int) => self.constant(Constant::Integer(*int)),
+            Litteral::Float(flt) => self.constant(Constant::Float(*flt)),
+            Litteral::Bool(bool) => self.constant(Constant::Bool(*bool)),
+            Litteral::List(list) => {
+                let count = self.nodes(list);
+                self.emit(Op::List(count));
+            }
+            Litteral::Map(map) => {
+                self.nodes(map.iter().map(|(_, value)| value));
+                let keys = map.iter().map(|(key, _)| key.clone()).collect();
+                self.chunk.keys.push(keys);
+                self.emit(Op::Map(self.chunk.keys.len() as u32 - 1));
+            }
+        }
+    }
+
+    fn lambda(&mut self, lambda: &Lambda) {
+        let chunk = compile_lambda(lambda);
+        let captures = chunk
+            .captured
+            .iter()
+            .map(|(name, _)| self.variable(name, None))
+            .collect();
+        self.chunk.prototypes.push(Prototype {
+            lambda: Rc::new(lambda.clone()),
+            chunk: Rc::new(chunk),
+            captures,
+        });
+        self.emit(Op::Closure(self.chunk.prototypes.len() as u32 - 1));
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use gc::Gc;
+
+use crate::{
+    runtime::{
+        bytecode::{Chunk, Op, Place, Variable},
+        resolve_function, Compiled, Scope,
+    },
+    Captures, Closure, ErrorKind, RuntimeError, Value,
+};
+
+use super::{expect_bool, get_field, get_index, set_field, set_index, Context};
+
+impl Context<'_> {
+    /// runs a chunk with its initial slots, leaving the scopes as they were.
+    pub(super) fn run_chunk(
+        &mut self,
+        chunk: &Chunk,
+        mut slots: Vec<Option<Value>>,
+    ) -> Result<Value, RuntimeError> {
+        let base = self.scopes.len();
+        let mut ip = 0;
+        let result = self.execute(chunk, &mut slots, &mut ip);
+        self.scopes.truncate(base);
+        result.map_err(|error| {
+            let node = ip.checked_sub(1).and_then(|ip| chunk.nodes[ip]);
+            self.set_node(node);
+            self.traced(error).at(node)
+        })
+    }
+
+    fn execute(
+        &mut self,
+        chunk: &Chunk,
+        slots: &mut [Option<Value>],
+        ip: &mut usize,
+    ) -> Result<Value, RuntimeError> {
+        let base = self.scopes.len();
+        let mut stack: Vec<Value> = vec![];
+        loop {
+            let op = chunk.ops[*ip];
+            *ip += 1;
+            match op {
+                Op::Constant(index) => {
+                    let value = chunk.constants[index as usize].value();
+                    self.allocate(&value)?;
+                    stack.push(value);
+                }
+                Op::PushNone => stack.push(Value::None),
+                Op::Pop => {
+                    stack.pop();
+                }
+                Op::Load(variable) => {
+                    let value = self.load(&chunk.variables[variable as usize], slots)?;
+                    stack.push(value);
+                }
+                Op::Assign(variable) => {
+                    let value = top(&stack).clone();
+                    self.assign(&chunk.variables[variable as usize], slots, chunk, value)?;
+                }
+                Op::Declare(place) => self.declare_at(place, slots, chunk, top(&stack).clone()),
+                Op::Item(path) => stack.push(self.find_item(&chunk.paths[path as usize])?),
+                Op::Index => {
+                    let index = pop(&mut stack);
+                    let target = pop(&mut stack);
+                    stack.push(get_index(&target, &index)?);
+                }
+                Op::SetIndex => {
+                    let value = pop(&mut stack);
+                    let index = pop(&mut stack);
+                    let target = pop(&mut stack);
+                    let grown = set_index(&target, &index, value.clone())?;
+                    self.charge(grown)?;
+                    stack.push(value);
+                }
+                Op::Field(name) => {
+                    let target = pop(&mut stack);
+                    stack.push(get_field(&target, &chunk.names[name as usize])?);
+                }
+                Op::SetField(name) => {
+                    let value = pop(&mut stack);
+                    let target = pop(&mut stack);
+                    let name = chunk.names[name as usize].clone();
+                    let grown = set_field(&target, name, value.clone())?;
+                    self.charge(grown)?;
+                    stack.push(value);
+                }
+                Op::List(count) => {
+                    let items = stack.split_off(stack.len() - count as usize);
+                    let value = Value::from(items);
+                    self.allocate(&value)?;
+                    stack.push(value);
+                }
+                Op::Map(keys) => {
+                    let keys = &chunk.keys[keys as usize];
+                    let values = stack.split_off(stack.len() - keys.len());
+                    let fields: HashMap<_, _> = keys.iter().cloned().zip(values).collect();
+                    let value = Value::from(fields);
+                    self.allocate(&value)?;
+                    stack.push(value);
+                }
+                Op::Call(site, count) => {
+                    let args = stack.split_off(stack.len() - count as usize);
+                    let site = &chunk.sites[site as usize];
+                    let cached = site.cache.borrow().clone();
+                    let handle = match cached {
+                        Some((generation, handle)) if generation == self.generation => handle,
+                        _ => {
+                            let requester = self.current_module.as_ref();
+                            let handle = resolve_function(self.modules, requester, &site.path)?;
+                            *site.cache.borrow_mut() = Some((self.generation, handle.clone()));
+                            handle
+                        }
+                    };
+                    self.set_node(chunk.nodes[*ip - 1]);
+                    stack.push(handle.call_with(self, args)?);
+                }
+                Op::CallValue(count) => {
+                    let args = stack.split_off(stack.len() - count as usize);
+                    let function = pop(&mut stack);
+                    self.set_node(chunk.nodes[*ip - 1]);
+                    stack.push(self.call_value(&function, args)?);
+                }
+                Op::PrepareMethod(name) => {
+                    let target = pop(&mut stack);
+                    if matches!(target, Value::UserData(_)) {
+                        stack.push(target);
+                        stack.push(true.into());
+                    } else {
+                        stack.push(get_field(&target, &chunk.names[name as usize])?);
+                        stack.push(false.into());
+                    }
+                }
+                Op::CallMethod(name, count) => {
+                    let args = stack.split_off(stack.len() - count as usize);
+                    let is_method = pop(&mut stack);
+                    let function = pop(&mut stack);
+                    self.set_node(chunk.nodes[*ip - 1]);
+                    let result = match is_method {
+                        Value::Bool(true) => {
+                            self.call_method(&function, &chunk.names[name as usize], args)?
+                        }
+                        _ => self.call_value(&function, args)?,
+                    };
+                    stack.push(result);
+                }
+                Op::Closure(prototype) => {
+                    let prototype = &chunk.prototypes[prototype as usize];
+                    let mut captures = Captures::default();
+                    for variable in &prototype.captures {
+                        let variable = &chunk.variables[*variable as usize];
+                        if let Some(value) = self.visible(variable, slots) {
+                            captures.insert(variable.name.clone(), value.clone());
+                        }
+                    }
+                    let closure = Closure {
+                        lambda: prototype.lambda.clone(),
+                        module: self.current_module.clone(),
+                        captures,
+                        code: Compiled::new(prototype.chunk.clone()),
+                    };
+                    stack.push(Value::Function(Gc::new(closure)));
+                }
+                Op::Binary(operator) => {
+                    let right = pop(&mut stack);
+                    let left = pop(&mut stack);
+                    let result = super::operators::binary(operator, left, right)?;
+                    self.allocate(&result)?;
+                    stack.push(result);
+                }
+                Op::Unary(operator) => {
+                    let operand = pop(&mut stack);
+                    stack.push(super::operators::unary(operator, operand)?);
+                }
+                Op::JumpIfFalse(target) => {
+                    if !expect_bool(pop(&mut stack))? {
+                        *ip = target as usize;
+                    }
+                }
+                Op::JumpIf(decisive, target) => {
+                    let value = expect_bool(pop(&mut stack))?;
+                    if value == decisive {
+                        stack.push(value.into());
+                        *ip = target as usize;
+                    }
+                }
+                Op::ExpectBool => {
+                    let value = expect_bool(pop(&mut stack))?;
+                    stack.push(value.into());
+                }
+                Op::Jump(target) => *ip = target as usize,
+                Op::EnterBlock(start, end) => {
+                    slots[start as usize..end as usize].fill(None);
+                    self.push_scope(Scope::new(true))?;
+                }
+                Op::ExitBlock => self.pop_scope(),
+                Op::Unwind(height, blocks) => {
+                    let value = pop(&mut stack);
+                    stack.truncate(height as usize);
+                    stack.push(value);
+                    self.scopes.truncate(base + blocks as usize);
+                }
+                Op::Return => return Ok(pop(&mut stack)),
+                Op::BreakOutsideLoop => return Err(ErrorKind::BreakOutsideLoop.into()),
+                Op::Fuel => self.consume_fuel()?,
+            }
+        }
+    }
+
+    /// value of a variable visible from the code, not looking into the
+    /// module.
+    fn visible<'v>(&'v self, variable: &Variable, slots: &'v [Option<Value>]) -> Option<&'v Value> {
+        let mut slot = variable.slots.iter();
+        let value = slot.find_map(|slot| slots[*slot as usize].as_ref());
+        match value {
+            Some(value) => Some(value),
+            None if variable.global => self.scopes[0].get(&variable.name),
+            None => None,
+        }
+    }
+
+    fn load(&self, variable: &Variable, slots: &[Option<Value>]) -> Result<Value, RuntimeError> {
+        if let Some(value) = self.visible(variable, slots) {
+            return Ok(value.clone());
+        }
+        self.local_module()
+            .and_then(|module| module.get_item(&variable.name))
+            .ok_or_else(|| ErrorKind::UndefinedVariable(variable.name.clone()).into())
+    }
+
+    /// updates the visible variable or else the global of the current module,
+    /// declaring the variable when there is neither.
+    fn assign(
+        &mut self,
+        variable: &Variable,
+        slots: &mut [Option<Value>],
+        chunk: &Chunk,
+        value: Value,
+    ) -> Result<(), RuntimeError> {
+        let name = &variable.name;
+        let mut slot = variable.slots.iter().map(|slot| *slot as usize);
+        if let Some(slot) = slot.find(|slot| slots[*slot].is_some()) {
+            slots[slot] = Some(value);
+        } else if let Some(global) = self.scopes[0].get_mut(name).filter(|_| variable.global) {
+            *global = value;
+        } else if let Some(module) = self.local_module().filter(|m| m.has_item(name)) {
+            module.set_item(name, value)?;
+        } else if let Some(place) = variable.place {
+            self.declare_at(place, slots, chunk, value);
+        }
+        Ok(())
+    }
+
+    fn declare_at(
+        &mut self,
+        place: Place,
+        slots: &mut [Option<Value>],
+        chunk: &Chunk,
+        value: Value,
+    ) {
+        match place {
+            Place::Slot(slot) => slots[slot as usize] = Some(value),
+            Place::Global(name) => self.scopes[0].insert(chunk.names[name as usize].clone(), value),
+        }
+    }
+}
+
+/// the compiler keeps track of the height of the stack, the values an
+/// instruction expects are always there.
+fn pop(stack: &mut Vec<Value>) -> Value {
+    stack.pop().expect("the stack holds the operands")
+}
+
+fn top(stack: &[Value]) -> &Value {
+    stack.last().expect("the stack holds the operands")
+}
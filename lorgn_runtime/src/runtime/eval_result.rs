@@ -13,10 +13,7 @@ impl EvRes {
     }
 
     pub fn is_short_circuit(&self) -> bool {
-        match self {
-            EvRes::Value(_) => false,
-            _ => true,
-        }
+        !matches!(self, EvRes::Value(_))
     }
 
     pub fn into_value(self) -> Option<Value> {
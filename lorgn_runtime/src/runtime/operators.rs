@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+
+use gc::Gc;
+
+use lorgn_lang::ast::{BinaryOperator, UnaryOperator};
+
+use super::grow_stack;
+use crate::{ErrorKind, RuntimeError, Value};
+
+/// applies an operator other than `and` and `or`, which short-circuit and
+/// are evaluated by the context.
+pub fn binary(operator: BinaryOperator, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    use BinaryOperator::*;
+
+    let result = match (operator, &left, &right) {
+        (Eq, _, _) => equals(&left, &right).into(),
+        (Ne, _, _) => (!equals(&left, &right)).into(),
+        (Lt | Le | Gt | Ge, _, _) => compare(operator, &left, &right)
+            .ok_or_else(|| invalid(operator, &left, &right))?
+            .into(),
+        (Add, Value::String(l), Value::String(r)) => format!("{l}{r}").into(),
+        (Add | Sub | Mul | Div | Rem, Value::Integer(l), Value::Integer(r)) => {
+            integer(operator, *l, *r)?.into()
+        }
+        (Add | Sub | Mul | Div | Rem, _, _) => {
+            let (l, r) = floats(&left, &right).ok_or_else(|| invalid(operator, &left, &right))?;
+            let result = match operator {
+                Add => l + r,
+                Sub => l - r,
+                Mul => l * r,
+                Div => l / r,
+                _ => l % r,
+            };
+            result.into()
+        }
+        (And | Or, _, _) => return Err(invalid(operator, &left, &right)),
+    };
+    Ok(result)
+}
+
+pub fn unary(operator: UnaryOperator, operand: Value) -> Result<Value, RuntimeError> {
+    let result = match (operator, &operand) {
+        (UnaryOperator::Neg, Value::Integer(int)) => int.wrapping_neg().into(),
+        (UnaryOperator::Neg, Value::Float(flt)) => (-flt).into(),
+        (UnaryOperator::Not, Value::Bool(bool)) => (!bool).into(),
+        _ => {
+            return Err(ErrorKind::InvalidOperand {
+                operator,
+                operand: operand.type_of(),
+            }
+            .into())
+        }
+    };
+    Ok(result)
+}
+
+/// integer arithmetic, wrapping on overflow.
+fn integer(operator: BinaryOperator, left: i32, right: i32) -> Result<i32, RuntimeError> {
+    let result = match operator {
+        BinaryOperator::Add => left.wrapping_add(right),
+        BinaryOperator::Sub => left.wrapping_sub(right),
+        BinaryOperator::Mul => left.wrapping_mul(right),
+        BinaryOperator::Div | BinaryOperator::Rem if right == 0 => {
+            return Err(ErrorKind::DivisionByZero.into())
+        }
+        BinaryOperator::Div => left.wrapping_div(right),
+        _ => left.wrapping_rem(right),
+    };
+    Ok(result)
+}
+
+/// both operands as floats, integers being promoted.
+fn floats(left: &Value, right: &Value) -> Option<(f32, f32)> {
+    let float = |value: &Value| match value {
+        Value::Integer(int) => Some(*int as f32),
+        Value::Float(flt) => Some(*flt),
+        _ => None,
+    };
+    Some((float(left)?, float(right)?))
+}
+
+/// equality used by the operators, integers and floats comparing by value
+/// and lists, objects and closures by content.
+///
+/// a pair of lists or objects met again while comparing them is taken as
+/// equal, so that values holding themselves can be compared.
+pub fn equals(left: &Value, right: &Value) -> bool {
+    Equality::default().values(left, right)
+}
+
+/// pairs of values being compared, by address.
+#[derive(Default)]
+struct Equality {
+    visited: HashSet<(usize, usize)>,
+}
+
+impl Equality {
+    fn values(&mut self, left: &Value, right: &Value) -> bool {
+        match (left, right) {
+            (Value::Integer(_), Value::Float(_)) | (Value::Float(_), Value::Integer(_)) => {
+                floats(left, right).is_some_and(|(l, r)| l == r)
+            }
+            (Value::List(l), Value::List(r)) => {
+                if Gc::ptr_eq(l, r) || !self.enter(&**l, &**r) {
+                    return true;
+                }
+                let (l, r) = (l.borrow(), r.borrow());
+                l.len() == r.len()
+                    && l.iter()
+                        .zip(r.iter())
+                        .all(|(l, r)| grow_stack(|| self.values(l, r)))
+            }
+            (Value::Object(l), Value::Object(r)) => {
+                if Gc::ptr_eq(l, r) || !self.enter(&**l, &**r) {
+                    return true;
+                }
+                let (l, r) = (l.borrow(), r.borrow());
+                let (l, r) = (l.fields(), r.fields());
+                l.len() == r.len()
+                    && l.iter().zip(&r).all(|((l_name, l), (r_name, r))| {
+                        l_name == r_name && grow_stack(|| self.values(l, r))
+                    })
+            }
+            (Value::Function(l), Value::Function(r)) => {
+                if Gc::ptr_eq(l, r) || !self.enter(&**l, &**r) {
+                    return true;
+                }
+                l.lambda == r.lambda
+                    && l.module == r.module
+                    && l.captures.iter().count() == r.captures.iter().count()
+                    && l.captures
+                        .iter()
+                        .all(|(name, l)| r.captures.get(name).is_some_and(|r| self.values(l, r)))
+            }
+            (Value::String(l), Value::String(r)) => l == r,
+            (Value::Integer(l), Value::Integer(r)) => l == r,
+            (Value::Float(l), Value::Float(r)) => l == r,
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (Value::UserData(l), Value::UserData(r)) => l == r,
+            (Value::None, Value::None) => true,
+            _ => false,
+        }
+    }
+
+    /// marks a pair as being compared, `false` when it already is.
+    fn enter<T>(&mut self, left: &T, right: &T) -> bool {
+        let address = |value: &T| value as *const T as usize;
+        self.visited.insert((address(left), address(right)))
+    }
+}
+
+/// applies an ordering operator, `None` when the operands cannot be
+/// compared. floats compare as in IEEE 754, NaN being neither less nor
+/// greater than any number.
+fn compare(operator: BinaryOperator, left: &Value, right: &Value) -> Option<bool> {
+    let ordering = match (left, right) {
+        (Value::Integer(l), Value::Integer(r)) => l.cmp(r),
+        (Value::String(l), Value::String(r)) => l.cmp(r),
+        _ => {
+            let (l, r) = floats(left, right)?;
+            let result = match operator {
+                BinaryOperator::Lt => l < r,
+                BinaryOperator::Le => l <= r,
+                BinaryOperator::Gt => l > r,
+                _ => l >= r,
+            };
+            return Some(result);
+        }
+    };
+    let result = match operator {
+        BinaryOperator::Lt => ordering.is_lt(),
+        BinaryOperator::Le => ordering.is_le(),
+        BinaryOperator::Gt => ordering.is_gt(),
+        _ => ordering.is_ge(),
+    };
+    Some(result)
+}
+
+fn invalid(operator: BinaryOperator, left: &Value, right: &Value) -> RuntimeError {
+    ErrorKind::InvalidOperands {
+        operator,
+        left: left.type_of(),
+        right: right.type_of(),
+    }
+    .into()
+}
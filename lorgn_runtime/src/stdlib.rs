@@ -0,0 +1,83 @@
+use gc::{Gc, GcCell};
+use lorgn_lang::typing::{Primitive, Signature, Type};
+
+use crate::{value::InnerObj, ErrorKind, Module, RuntimeError, Value};
+
+mod convert;
+pub mod io;
+mod list;
+mod math;
+mod object;
+mod string;
+
+/// modules of the standard library, each named `std::<module>`.
+pub fn modules() -> Vec<Module> {
+    vec![
+        math::module(),
+        string::module(),
+        list::module(),
+        object::module(),
+        convert::module(),
+        io::module(),
+    ]
+}
+
+const STRING: Type = Type::Primitive(Primitive::String);
+const INTEGER: Type = Type::Primitive(Primitive::Integer);
+const FLOAT: Type = Type::Primitive(Primitive::Float);
+const BOOL: Type = Type::Primitive(Primitive::Bool);
+
+fn signature<const N: usize>(parameters: [Type; N], result: Type) -> Signature {
+    Signature::new(parameters.into(), result)
+}
+
+fn mismatch(expected: &'static str, found: &Value) -> RuntimeError {
+    let found = found.type_name();
+    ErrorKind::TypeMismatch { expected, found }.into()
+}
+
+/// list given as an argument, shared with the caller.
+fn list(value: &Value) -> Result<&Gc<GcCell<Vec<Value>>>, RuntimeError> {
+    match value {
+        Value::List(list) => Ok(list),
+        other => Err(mismatch("list", other)),
+    }
+}
+
+/// object given as an argument, shared with the caller.
+fn object(value: &Value) -> Result<&Gc<GcCell<InnerObj>>, RuntimeError> {
+    match value {
+        Value::Object(object) => Ok(object),
+        other => Err(mismatch("object", other)),
+    }
+}
+
+#[cfg(test)]
+fn eval(source: &str) -> crate::Value {
+    let mut runtime = crate::Runtime::with_std();
+    let expr = lorgn_lang::parser::parse_expr(source).unwrap().expr;
+    runtime.evaluate(expr).unwrap()
+}
+
+/// kind of the error raised by an expression.
+#[cfg(test)]
+fn eval_error(source: &str) -> ErrorKind {
+    let mut runtime = crate::Runtime::with_std();
+    let expr = lorgn_lang::parser::parse_expr(source).unwrap().expr;
+    runtime.evaluate(expr).unwrap_err().kind
+}
+
+#[cfg(test)]
+fn type_mismatch(expected: &'static str, found: &'static str) -> ErrorKind {
+    ErrorKind::TypeMismatch { expected, found }
+}
+
+#[cfg(test)]
+fn string(text: &str) -> Value {
+    Value::from(text.to_string())
+}
+
+#[cfg(test)]
+fn integers(items: &[i32]) -> Value {
+    Value::from(items.iter().map(|&item| item.into()).collect::<Vec<_>>())
+}
@@ -0,0 +1,100 @@
+use lorgn_lang::typing::Type;
+
+use crate::{Module, RuntimeError, Value};
+
+use super::{mismatch, signature, STRING};
+
+pub fn module() -> Module {
+    let mut module = Module::new_empty("std::convert");
+    module
+        .push_typed_native("to_string".into(), to_string)
+        .set_signature(signature([Type::Any], STRING));
+    module
+        .push_typed_native("to_integer".into(), to_integer)
+        .set_signature(signature([Type::Any], Type::Any));
+    module
+        .push_typed_native("to_float".into(), to_float)
+        .set_signature(signature([Type::Any], Type::Any));
+    module
+        .push_typed_native("type_of".into(), type_of)
+        .set_signature(signature([Type::Any], STRING));
+    module
+}
+
+/// textual representation of a value.
+fn to_string(value: Value) -> String {
+    value.to_string()
+}
+
+/// integer from a number, truncating floats, a boolean or a string, `none`
+/// when the string is not an integer.
+fn to_integer(value: Value) -> Result<Option<i32>, RuntimeError> {
+    match &value {
+        &Value::Integer(int) => Ok(Some(int)),
+        &Value::Float(flt) => Ok(Some(flt as i32)),
+        &Value::Bool(bool) => Ok(Some(bool as i32)),
+        Value::String(str) => Ok(str.trim().parse::<i32>().ok()),
+        other => Err(mismatch("number, bool or string", other)),
+    }
+}
+
+/// float from a number or a string, `none` when the string is not a number.
+fn to_float(value: Value) -> Result<Option<f32>, RuntimeError> {
+    match &value {
+        &Value::Integer(int) => Ok(Some(int as f32)),
+        &Value::Float(flt) => Ok(Some(flt)),
+        Value::String(str) => Ok(str.trim().parse::<f32>().ok()),
+        other => Err(mismatch("number or string", other)),
+    }
+}
+
+/// name of the type of a value.
+fn type_of(value: Value) -> &'static str {
+    value.type_name()
+}
+
+#[test]
+fn test_to_string() {
+    use super::{eval, string};
+
+    assert_eq!(eval("std::convert::to_string(1.5)"), string("1.5"));
+    assert_eq!(
+        eval(r#"std::convert::to_string([1, "a", #{ b: true }])"#),
+        string(r#"[1, "a", #{ b: true }]"#)
+    );
+}
+
+#[test]
+fn test_to_integer() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::convert::to_integer(2.7)"), 2.into());
+    assert_eq!(eval("std::convert::to_integer(true)"), 1.into());
+    assert_eq!(eval(r#"std::convert::to_integer(" 12 ")"#), 12.into());
+    assert_eq!(eval(r#"std::convert::to_integer("a")"#), Value::None);
+    assert_eq!(
+        eval_error("std::convert::to_integer([])"),
+        type_mismatch("number, bool or string", "list")
+    );
+}
+
+#[test]
+fn test_to_float() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::convert::to_float(2)"), 2.0.into());
+    assert_eq!(eval(r#"std::convert::to_float("1.5")"#), 1.5.into());
+    assert_eq!(eval(r#"std::convert::to_float("a")"#), Value::None);
+    assert_eq!(
+        eval_error("std::convert::to_float([])"),
+        type_mismatch("number or string", "list")
+    );
+}
+
+#[test]
+fn test_type_of() {
+    use super::{eval, string};
+
+    assert_eq!(eval("std::convert::type_of(#{})"), string("object"));
+    assert_eq!(eval("std::convert::type_of(1.5)"), string("float"));
+}
@@ -0,0 +1,119 @@
+use std::{
+    cell::RefCell,
+    io::{BufRead, BufReader, Write},
+    rc::Rc,
+};
+
+use lorgn_lang::typing::Type;
+
+use crate::{Module, Value};
+
+use super::signature;
+
+/// `std::io` reading the standard input and writing the standard output.
+pub fn module() -> Module {
+    module_with(BufReader::new(std::io::stdin()), std::io::stdout())
+}
+
+/// `std::io` reading from `input` and writing to `output`.
+pub fn module_with(input: impl BufRead + 'static, output: impl Write + 'static) -> Module {
+    let output = Rc::new(RefCell::new(output));
+    let mut module = Module::new_empty("std::io");
+    let print_output = output.clone();
+    module
+        .push_native("print".into(), move |[value]: [Value; 1]| {
+            print(&mut *print_output.borrow_mut(), value, "")
+        })
+        .set_signature(signature([Type::Any], Type::None));
+    module
+        .push_native("println".into(), move |[value]: [Value; 1]| {
+            print(&mut *output.borrow_mut(), value, "\n")
+        })
+        .set_signature(signature([Type::Any], Type::None));
+    let mut input = input;
+    module
+        .push_typed_native("read_line".into(), move || read_line(&mut input))
+        .set_signature(signature([], Type::Any));
+    module
+}
+
+/// writes a value followed by `end`, write errors being ignored.
+fn print(output: &mut impl Write, value: Value, end: &str) -> Value {
+    let _ = write!(output, "{value}{end}").and_then(|_| output.flush());
+    Value::None
+}
+
+/// next line of the input without its line break, `none` at the end of the
+/// input.
+fn read_line(input: &mut impl BufRead) -> Option<String> {
+    let mut line = String::new();
+    match input.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+    }
+}
+
+/// output shared with the test once the module is registered.
+#[cfg(test)]
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+
+#[cfg(test)]
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// runtime with `std::io` reading `input`, along with its output.
+#[cfg(test)]
+fn runtime_with(input: &'static str) -> (crate::Runtime, Shared) {
+    let output = Shared::default();
+    let mut runtime = crate::Runtime::default();
+    runtime
+        .register(module_with(std::io::Cursor::new(input), output.clone()))
+        .unwrap();
+    (runtime, output)
+}
+
+#[cfg(test)]
+fn eval(runtime: &mut crate::Runtime, source: &str) -> Value {
+    let expr = lorgn_lang::parser::parse_expr(source).unwrap().expr;
+    runtime.evaluate(expr).unwrap()
+}
+
+#[test]
+fn test_print() {
+    let (mut runtime, output) = runtime_with("");
+    assert_eq!(eval(&mut runtime, r#"std::io::print("a")"#), Value::None);
+    assert_eq!(eval(&mut runtime, "std::io::print(1.5)"), Value::None);
+    let written = String::from_utf8(output.0.borrow().clone()).unwrap();
+    assert_eq!(written, "a1.5");
+}
+
+#[test]
+fn test_println() {
+    let (mut runtime, output) = runtime_with("");
+    assert_eq!(
+        eval(&mut runtime, r#"std::io::println(["b", 1])"#),
+        Value::None
+    );
+    assert_eq!(eval(&mut runtime, "std::io::println(#{})"), Value::None);
+    let written = String::from_utf8(output.0.borrow().clone()).unwrap();
+    assert_eq!(written, "[\"b\", 1]\n#{}\n");
+}
+
+#[test]
+fn test_read_line() {
+    use super::string;
+
+    let (mut runtime, output) = runtime_with("first\r\nsecond");
+    assert_eq!(eval(&mut runtime, "std::io::read_line()"), string("first"));
+    assert_eq!(eval(&mut runtime, "std::io::read_line()"), string("second"));
+    assert_eq!(eval(&mut runtime, "std::io::read_line()"), Value::None);
+    assert!(output.0.borrow().is_empty());
+}
@@ -0,0 +1,320 @@
+use lorgn_lang::typing::Type;
+
+use crate::{
+    runtime::{equals, Context, ITEM_SIZE},
+    FromValue, Module, RuntimeError, Value,
+};
+
+use super::{list, mismatch, signature, BOOL, INTEGER};
+
+pub fn module() -> Module {
+    let mut module = Module::new_empty("std::list");
+    module
+        .push_typed_native("length".into(), length)
+        .set_signature(signature([Type::List], INTEGER));
+    module
+        .push_typed_native("get".into(), get)
+        .set_signature(signature([Type::List, INTEGER], Type::Any));
+    module
+        .push_contextual_native("push".into(), push)
+        .set_signature(signature([Type::List, Type::Any], Type::None));
+    module
+        .push_typed_native("pop".into(), pop)
+        .set_signature(signature([Type::List], Type::Any));
+    module
+        .push_typed_native("contains".into(), contains)
+        .set_signature(signature([Type::List, Type::Any], BOOL));
+    module
+        .push_typed_native("concat".into(), concat)
+        .set_signature(signature([Type::List, Type::List], Type::List));
+    module
+        .push_typed_native("reverse".into(), reverse)
+        .set_signature(signature([Type::List], Type::List));
+    module
+        .push_contextual_native("range".into(), range)
+        .set_signature(signature([INTEGER, INTEGER], Type::List));
+    module
+        .push_contextual_native("map".into(), map)
+        .set_signature(signature([Type::List, Type::Function], Type::List));
+    module
+        .push_contextual_native("filter".into(), filter)
+        .set_signature(signature([Type::List, Type::Function], Type::List));
+    module
+        .push_contextual_native("fold".into(), fold)
+        .set_signature(signature(
+            [Type::List, Type::Any, Type::Function],
+            Type::Any,
+        ));
+    module
+}
+
+/// copy of the items of a list, so that callbacks may modify it.
+fn items(value: &Value) -> Result<Vec<Value>, RuntimeError> {
+    list(value).map(|list| list.borrow().clone())
+}
+
+/// new list with a function applied to each item.
+fn map(context: &mut Context, [list, function]: [Value; 2]) -> Result<Value, RuntimeError> {
+    let items = items(&list)?;
+    let mut result = Vec::with_capacity(items.len());
+    for item in items {
+        result.push(context.call_value(&function, vec![item])?);
+    }
+    let result = result.into();
+    context.allocate(&result)?;
+    Ok(result)
+}
+
+/// new list with the items for which a function returns `true`.
+fn filter(context: &mut Context, [list, function]: [Value; 2]) -> Result<Value, RuntimeError> {
+    let mut result = vec![];
+    for item in items(&list)? {
+        match context.call_value(&function, vec![item.clone()])? {
+            Value::Bool(true) => result.push(item),
+            Value::Bool(false) => (),
+            other => return Err(mismatch("bool", &other)),
+        }
+    }
+    let result = result.into();
+    context.allocate(&result)?;
+    Ok(result)
+}
+
+/// combines the items from the first one with a function taking the
+/// accumulated value and the item.
+fn fold(
+    context: &mut Context,
+    [list, initial, function]: [Value; 3],
+) -> Result<Value, RuntimeError> {
+    let mut result = initial;
+    for item in items(&list)? {
+        result = context.call_value(&function, vec![result, item])?;
+    }
+    Ok(result)
+}
+
+/// number of items of a list.
+fn length(value: Value) -> Result<i32, RuntimeError> {
+    Ok(list(&value)?.borrow().len() as i32)
+}
+
+/// item at an index, `none` when out of range.
+fn get(value: Value, index: i32) -> Result<Option<Value>, RuntimeError> {
+    let list = list(&value)?.borrow();
+    let index = usize::try_from(index).ok();
+    Ok(index.and_then(|index| list.get(index).cloned()))
+}
+
+/// appends an item at the end of a list.
+fn push(context: &mut Context, [value, item]: [Value; 2]) -> Result<Value, RuntimeError> {
+    let list = list(&value)?;
+    context.charge(ITEM_SIZE)?;
+    list.borrow_mut().push(item);
+    Ok(Value::None)
+}
+
+/// removes the last item of a list and returns it, `none` when empty.
+fn pop(value: Value) -> Result<Option<Value>, RuntimeError> {
+    Ok(list(&value)?.borrow_mut().pop())
+}
+
+/// whether a list holds an item equal to a value.
+fn contains(value: Value, item: Value) -> Result<bool, RuntimeError> {
+    let list = list(&value)?.borrow();
+    Ok(list.iter().any(|value| equals(value, &item)))
+}
+
+/// new list with the items of a list followed by those of another.
+fn concat(mut first: Vec<Value>, second: Vec<Value>) -> Vec<Value> {
+    first.extend(second);
+    first
+}
+
+/// new list with the items of a list in reverse order.
+fn reverse(mut list: Vec<Value>) -> Vec<Value> {
+    list.reverse();
+    list
+}
+
+/// integers from a start up to an end excluded.
+fn range(context: &mut Context, [start, end]: [Value; 2]) -> Result<Value, RuntimeError> {
+    let (start, end) = (i32::from_value(start)?, i32::from_value(end)?);
+    // the list is accounted for before being built.
+    let length = (i64::from(end) - i64::from(start)).max(0) as usize;
+    context.charge(length.saturating_mul(ITEM_SIZE))?;
+    let result: Vec<Value> = (start..end).map(Value::from).collect();
+    Ok(result.into())
+}
+
+#[test]
+fn test_length() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::list::length([1, 2, 3])"), 3.into());
+    assert_eq!(eval("std::list::length([])"), 0.into());
+    assert_eq!(
+        eval_error("std::list::length(1)"),
+        type_mismatch("list", "integer")
+    );
+}
+
+#[test]
+fn test_get() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::list::get([1, 2], 1)"), 2.into());
+    assert_eq!(eval("std::list::get([1, 2], 2)"), Value::None);
+    assert_eq!(eval("std::list::get([1, 2], -1)"), Value::None);
+    assert_eq!(
+        eval_error(r#"std::list::get([1, 2], "1")"#),
+        type_mismatch("integer", "string")
+    );
+    assert_eq!(
+        eval_error("std::list::get(#{}, 0)"),
+        type_mismatch("list", "object")
+    );
+}
+
+#[test]
+fn test_push() {
+    use super::{eval, eval_error, integers, type_mismatch};
+
+    assert_eq!(
+        eval("fn(l) { std::list::push(l, 3); l }([1, 2])"),
+        integers(&[1, 2, 3])
+    );
+    assert_eq!(
+        eval_error(r#"std::list::push("ab", 3)"#),
+        type_mismatch("list", "string")
+    );
+}
+
+#[test]
+fn test_pop() {
+    use super::{eval, eval_error, integers, type_mismatch};
+
+    assert_eq!(
+        eval("fn(l) { [std::list::pop(l), l] }([1, 2])"),
+        vec![2.into(), integers(&[1])].into()
+    );
+    assert_eq!(eval("std::list::pop([])"), Value::None);
+    assert_eq!(
+        eval_error("std::list::pop(1.5)"),
+        type_mismatch("list", "float")
+    );
+}
+
+#[test]
+fn test_contains() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::list::contains([1, [2]], [2])"), true.into());
+    assert_eq!(eval("std::list::contains([1], 2)"), false.into());
+    assert_eq!(eval("std::list::contains([1, [2]], [2.0])"), true.into());
+    assert_eq!(
+        eval("{ a = [1]; std::list::push(a, a); std::list::contains([a], a) }"),
+        true.into()
+    );
+    assert_eq!(
+        eval_error(r#"std::list::contains("abc", "a")"#),
+        type_mismatch("list", "string")
+    );
+}
+
+#[test]
+fn test_concat() {
+    use super::{eval, eval_error, integers, type_mismatch};
+
+    assert_eq!(eval("std::list::concat([1], [2, 3])"), integers(&[1, 2, 3]));
+    assert_eq!(
+        eval("{ l = [1]; std::list::concat(l, [2]); l }"),
+        integers(&[1])
+    );
+    assert_eq!(
+        eval_error("std::list::concat([1], 2)"),
+        type_mismatch("list", "integer")
+    );
+}
+
+#[test]
+fn test_reverse() {
+    use super::{eval, eval_error, integers, type_mismatch};
+
+    assert_eq!(eval("std::list::reverse([1, 2, 3])"), integers(&[3, 2, 1]));
+    assert_eq!(
+        eval_error("std::list::reverse(#{})"),
+        type_mismatch("list", "object")
+    );
+}
+
+#[test]
+fn test_range() {
+    use super::{eval, eval_error, integers, type_mismatch};
+
+    assert_eq!(eval("std::list::range(1, 4)"), integers(&[1, 2, 3]));
+    assert_eq!(eval("std::list::range(4, 1)"), integers(&[]));
+    assert_eq!(
+        eval_error("std::list::range(0, 1.5)"),
+        type_mismatch("integer", "float")
+    );
+}
+
+#[test]
+fn test_map() {
+    use super::{eval, eval_error, integers, type_mismatch};
+    use crate::ErrorKind;
+
+    assert_eq!(
+        eval("std::list::map([1, 2, 3], fn(x) { x * 2 })"),
+        integers(&[2, 4, 6])
+    );
+    assert_eq!(
+        eval("std::list::map([[1], [2, 3]], fn(l) { std::list::map(l, fn(x) { x + 1 }) })"),
+        vec![integers(&[2]), integers(&[3, 4])].into()
+    );
+    assert_eq!(
+        eval_error("std::list::map(1, fn(x) { x })"),
+        type_mismatch("list", "integer")
+    );
+    assert_eq!(
+        eval_error("std::list::map([1], 2)"),
+        type_mismatch("function", "integer")
+    );
+    assert_eq!(
+        eval_error("std::list::map([0], fn(x) { 1 / x })"),
+        ErrorKind::DivisionByZero
+    );
+}
+
+#[test]
+fn test_filter() {
+    use super::{eval, eval_error, integers, type_mismatch};
+
+    assert_eq!(
+        eval("std::list::filter([1, 2, 3, 4], fn(x) { x % 2 == 0 })"),
+        integers(&[2, 4])
+    );
+    assert_eq!(
+        eval_error("std::list::filter([1], fn(x) { x })"),
+        type_mismatch("bool", "integer")
+    );
+    assert_eq!(
+        eval_error("std::list::filter(#{}, fn(x) { true })"),
+        type_mismatch("list", "object")
+    );
+}
+
+#[test]
+fn test_fold() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(
+        eval("std::list::fold([1, 2, 3], 10, fn(acc, x) { acc + x })"),
+        16.into()
+    );
+    assert_eq!(eval("std::list::fold([], 10, 1)"), 10.into());
+    assert_eq!(
+        eval_error("std::list::fold(1, 10, fn(acc, x) { acc })"),
+        type_mismatch("list", "integer")
+    );
+}
@@ -0,0 +1,239 @@
+use lorgn_lang::typing::{Arity, Type};
+
+use crate::{runtime::Context, Module, RuntimeError, Value};
+
+use super::{mismatch, signature, FLOAT, INTEGER};
+
+pub fn module() -> Module {
+    let mut module = Module::new_empty("std::math");
+    module
+        .push_typed_native("pi".into(), pi)
+        .set_signature(signature([], FLOAT));
+    module
+        .push_typed_native("abs".into(), abs)
+        .set_signature(signature([Type::Any], Type::Any));
+    module
+        .push_contextual_variadic_native("min".into(), Arity::at_least(1), min)
+        .set_signature(signature([Type::Any], Type::Any).with_rest(Type::Any));
+    module
+        .push_contextual_variadic_native("max".into(), Arity::at_least(1), max)
+        .set_signature(signature([Type::Any], Type::Any).with_rest(Type::Any));
+    module
+        .push_typed_native("floor".into(), floor)
+        .set_signature(signature([Type::Any], INTEGER));
+    module
+        .push_typed_native("ceil".into(), ceil)
+        .set_signature(signature([Type::Any], INTEGER));
+    module
+        .push_typed_native("round".into(), round)
+        .set_signature(signature([Type::Any], INTEGER));
+    module
+        .push_typed_native("sqrt".into(), sqrt)
+        .set_signature(signature([Type::Any], FLOAT));
+    module
+        .push_typed_native("pow".into(), pow)
+        .set_signature(signature([Type::Any, Type::Any], Type::Any));
+    module
+}
+
+/// the ratio of a circle circumference to its diameter.
+fn pi() -> f32 {
+    std::f32::consts::PI
+}
+
+/// absolute value of a number.
+fn abs(x: Value) -> Result<Value, RuntimeError> {
+    match x {
+        Value::Integer(int) => Ok(int.wrapping_abs().into()),
+        Value::Float(flt) => Ok(flt.abs().into()),
+        other => Err(mismatch("number", &other)),
+    }
+}
+
+/// smallest of some numbers, an integer when all are.
+fn min(_: &mut Context, values: Vec<Value>) -> Result<Value, RuntimeError> {
+    reduce(values, i32::min, f32::min)
+}
+
+/// greatest of some numbers, an integer when all are.
+fn max(_: &mut Context, values: Vec<Value>) -> Result<Value, RuntimeError> {
+    reduce(values, i32::max, f32::max)
+}
+
+/// greatest integer less than or equal to a number.
+fn floor(x: Value) -> Result<i32, RuntimeError> {
+    rounded(x, f32::floor)
+}
+
+/// least integer greater than or equal to a number.
+fn ceil(x: Value) -> Result<i32, RuntimeError> {
+    rounded(x, f32::ceil)
+}
+
+/// nearest integer to a number, halves rounding away from zero.
+fn round(x: Value) -> Result<i32, RuntimeError> {
+    rounded(x, f32::round)
+}
+
+/// square root of a number, as a float.
+fn sqrt(x: Value) -> Result<f32, RuntimeError> {
+    number(x).map(f32::sqrt)
+}
+
+/// number raised to a power, an integer when both are and the exponent is
+/// not negative.
+fn pow(base: Value, exponent: Value) -> Result<Value, RuntimeError> {
+    match (&base, &exponent) {
+        (Value::Integer(base), Value::Integer(exponent)) if *exponent >= 0 => {
+            Ok(base.wrapping_pow(*exponent as u32).into())
+        }
+        _ => Ok(number(base)?.powf(number(exponent)?).into()),
+    }
+}
+
+/// number as a float, integers being promoted.
+fn number(x: Value) -> Result<f32, RuntimeError> {
+    match x {
+        Value::Integer(int) => Ok(int as f32),
+        Value::Float(flt) => Ok(flt),
+        other => Err(mismatch("number", &other)),
+    }
+}
+
+fn numbers(
+    a: Value,
+    b: Value,
+    int: fn(i32, i32) -> i32,
+    flt: fn(f32, f32) -> f32,
+) -> Result<Value, RuntimeError> {
+    match (&a, &b) {
+        (Value::Integer(a), Value::Integer(b)) => Ok(int(*a, *b).into()),
+        _ => Ok(flt(number(a)?, number(b)?).into()),
+    }
+}
+
+fn reduce(
+    values: Vec<Value>,
+    int: fn(i32, i32) -> i32,
+    flt: fn(f32, f32) -> f32,
+) -> Result<Value, RuntimeError> {
+    let mut values = values.into_iter();
+    let first = values.next().unwrap_or(Value::None);
+    // a single number is checked as well.
+    let first = numbers(first.clone(), first, int, flt)?;
+    values.try_fold(first, |result, value| numbers(result, value, int, flt))
+}
+
+fn rounded(x: Value, rounding: fn(f32) -> f32) -> Result<i32, RuntimeError> {
+    match x {
+        Value::Integer(int) => Ok(int),
+        Value::Float(flt) => Ok(rounding(flt) as i32),
+        other => Err(mismatch("number", &other)),
+    }
+}
+
+#[test]
+fn test_pi() {
+    use super::eval;
+
+    assert_eq!(eval("std::math::pi()"), std::f32::consts::PI.into());
+}
+
+#[test]
+fn test_abs() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::math::abs(-3)"), 3.into());
+    assert_eq!(eval("std::math::abs(-1.5)"), 1.5.into());
+    assert_eq!(
+        eval_error("std::math::abs(\"a\")"),
+        type_mismatch("number", "string")
+    );
+}
+
+#[test]
+fn test_min() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::math::min(2, 1)"), 1.into());
+    assert_eq!(eval("std::math::min(2, 1.5)"), 1.5.into());
+    assert_eq!(eval("std::math::min(4, 2, 3, 5)"), 2.into());
+    assert_eq!(
+        eval_error("std::math::min([])"),
+        type_mismatch("number", "list")
+    );
+}
+
+#[test]
+fn test_max() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::math::max(2, 1)"), 2.into());
+    assert_eq!(eval("std::math::max(2, 2.5)"), 2.5.into());
+    assert_eq!(eval("std::math::max(3)"), 3.into());
+    assert_eq!(eval("std::math::max(1, 2.5, 2)"), 2.5.into());
+    assert_eq!(
+        eval_error("std::math::max(1, \"a\", 2)"),
+        type_mismatch("number", "string")
+    );
+}
+
+#[test]
+fn test_floor() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::math::floor(-1.5)"), (-2).into());
+    assert_eq!(eval("std::math::floor(3)"), 3.into());
+    assert_eq!(
+        eval_error("std::math::floor(true)"),
+        type_mismatch("number", "bool")
+    );
+}
+
+#[test]
+fn test_ceil() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::math::ceil(1.2)"), 2.into());
+    assert_eq!(
+        eval_error("std::math::ceil(\"1\")"),
+        type_mismatch("number", "string")
+    );
+}
+
+#[test]
+fn test_round() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::math::round(2.5)"), 3.into());
+    assert_eq!(eval("std::math::round(4)"), 4.into());
+    assert_eq!(
+        eval_error("std::math::round(#{})"),
+        type_mismatch("number", "object")
+    );
+}
+
+#[test]
+fn test_sqrt() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::math::sqrt(9)"), 3.0.into());
+    assert_eq!(eval("std::math::sqrt(2.25)"), 1.5.into());
+    assert_eq!(
+        eval_error("std::math::sqrt(\"9\")"),
+        type_mismatch("number", "string")
+    );
+}
+
+#[test]
+fn test_pow() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval("std::math::pow(2, 10)"), 1024.into());
+    assert_eq!(eval("std::math::pow(4, -1)"), 0.25.into());
+    assert_eq!(eval("std::math::pow(4, 0.5)"), 2.0.into());
+    assert_eq!(
+        eval_error("std::math::pow(2, [])"),
+        type_mismatch("number", "list")
+    );
+}
@@ -0,0 +1,168 @@
+use lorgn_lang::typing::Type;
+
+use crate::{
+    runtime::{Context, FIELD_SIZE},
+    FromValue, Module, RuntimeError, Value,
+};
+
+use super::{object, signature, BOOL, STRING};
+
+pub fn module() -> Module {
+    let mut module = Module::new_empty("std::object");
+    module
+        .push_typed_native("keys".into(), keys)
+        .set_signature(signature([Type::Object], Type::List));
+    module
+        .push_typed_native("values".into(), values)
+        .set_signature(signature([Type::Object], Type::List));
+    module
+        .push_typed_native("has".into(), has)
+        .set_signature(signature([Type::Object, STRING], BOOL));
+    module
+        .push_typed_native("get".into(), get)
+        .set_signature(signature([Type::Object, STRING], Type::Any));
+    module
+        .push_contextual_native("set".into(), set)
+        .set_signature(signature([Type::Object, STRING, Type::Any], Type::None));
+    module
+        .push_typed_native("remove".into(), remove)
+        .set_signature(signature([Type::Object, STRING], Type::Any));
+    module
+}
+
+/// names of the fields of an object, in alphabetical order.
+fn keys(value: Value) -> Result<Vec<String>, RuntimeError> {
+    let object = object(&value)?.borrow();
+    Ok(object
+        .fields()
+        .into_iter()
+        .map(|(key, _)| key.0.clone())
+        .collect())
+}
+
+/// values of the fields of an object, in the alphabetical order of their
+/// names.
+fn values(value: Value) -> Result<Vec<Value>, RuntimeError> {
+    let object = object(&value)?.borrow();
+    Ok(object
+        .fields()
+        .into_iter()
+        .map(|(_, value)| value.clone())
+        .collect())
+}
+
+/// whether an object has a field.
+fn has(value: Value, key: String) -> Result<bool, RuntimeError> {
+    Ok(object(&value)?.borrow().contains(&key.into()))
+}
+
+/// value of a field, `none` when it is missing.
+fn get(value: Value, key: String) -> Result<Option<Value>, RuntimeError> {
+    Ok(object(&value)?.borrow().get(&key.into()).cloned())
+}
+
+/// sets a field, adding it when it is missing.
+fn set(context: &mut Context, [value, key, item]: [Value; 3]) -> Result<Value, RuntimeError> {
+    let object = object(&value)?;
+    let key = String::from_value(key)?.into();
+    if !object.borrow().contains(&key) {
+        context.charge(FIELD_SIZE)?;
+    }
+    object.borrow_mut().insert(key, item);
+    Ok(Value::None)
+}
+
+/// removes a field and returns its value, `none` when it is missing.
+fn remove(value: Value, key: String) -> Result<Option<Value>, RuntimeError> {
+    Ok(object(&value)?.borrow_mut().remove(&key.into()))
+}
+
+#[test]
+fn test_keys() {
+    use super::{eval, eval_error, string, type_mismatch};
+
+    assert_eq!(
+        eval("std::object::keys(#{ b: 1, a: 2 })"),
+        vec![string("a"), string("b")].into()
+    );
+    assert_eq!(eval("std::object::keys(#{})"), Vec::<Value>::new().into());
+    assert_eq!(
+        eval_error("std::object::keys([1])"),
+        type_mismatch("object", "list")
+    );
+}
+
+#[test]
+fn test_values() {
+    use super::{eval, eval_error, integers, type_mismatch};
+
+    assert_eq!(
+        eval("std::object::values(#{ b: 1, a: 2 })"),
+        integers(&[2, 1])
+    );
+    assert_eq!(
+        eval_error("std::object::values(1)"),
+        type_mismatch("object", "integer")
+    );
+}
+
+#[test]
+fn test_has() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval(r#"std::object::has(#{ a: 1 }, "a")"#), true.into());
+    assert_eq!(eval(r#"std::object::has(#{ a: 1 }, "b")"#), false.into());
+    assert_eq!(
+        eval_error("std::object::has(#{ a: 1 }, 1)"),
+        type_mismatch("string", "integer")
+    );
+}
+
+#[test]
+fn test_get() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval(r#"std::object::get(#{ a: 1 }, "a")"#), 1.into());
+    assert_eq!(eval(r#"std::object::get(#{ a: 1 }, "b")"#), Value::None);
+    assert_eq!(
+        eval_error(r#"std::object::get([1], "a")"#),
+        type_mismatch("object", "list")
+    );
+}
+
+#[test]
+fn test_set() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(
+        eval(r#"fn(o) { std::object::set(o, "b", 2); o.b }(#{ a: 1 })"#),
+        2.into()
+    );
+    assert_eq!(
+        eval(r#"fn(o) { std::object::set(o, "a", 2); o.a }(#{ a: 1 })"#),
+        2.into()
+    );
+    assert_eq!(
+        eval_error(r#"std::object::set("o", "a", 1)"#),
+        type_mismatch("object", "string")
+    );
+    assert_eq!(
+        eval_error("std::object::set(#{}, 1, 1)"),
+        type_mismatch("string", "integer")
+    );
+}
+
+#[test]
+fn test_remove() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(
+        eval(r#"fn(o) { [std::object::remove(o, "a"), std::object::keys(o)] }(#{ a: 1 })"#),
+        vec![1.into(), Vec::<Value>::new().into()].into()
+    );
+    assert_eq!(eval(r#"std::object::remove(#{}, "a")"#), Value::None);
+    assert_eq!(
+        eval_error(r#"std::object::remove(1, "a")"#),
+        type_mismatch("object", "integer")
+    );
+}
@@ -0,0 +1,254 @@
+use lorgn_lang::typing::{Arity, Type};
+
+use crate::{runtime::Context, FromValue, Module, RuntimeError, Value};
+
+use super::{signature, BOOL, INTEGER, STRING};
+
+pub fn module() -> Module {
+    let mut module = Module::new_empty("std::string");
+    module
+        .push_typed_native("length".into(), length)
+        .set_signature(signature([STRING], INTEGER));
+    module
+        .push_typed_native("upper".into(), upper)
+        .set_signature(signature([STRING], STRING));
+    module
+        .push_typed_native("lower".into(), lower)
+        .set_signature(signature([STRING], STRING));
+    module
+        .push_typed_native("trim".into(), trim)
+        .set_signature(signature([STRING], STRING));
+    module
+        .push_typed_native("contains".into(), contains)
+        .set_signature(signature([STRING, STRING], BOOL));
+    module
+        .push_typed_native("replace".into(), replace)
+        .set_signature(signature([STRING, STRING, STRING], STRING));
+    module
+        .push_contextual_variadic_native("slice".into(), Arity::range(2, 3), slice)
+        .set_signature(signature([STRING, INTEGER, INTEGER], Type::Any).with_optional(1));
+    module
+        .push_typed_native("split".into(), split)
+        .set_signature(signature([STRING, STRING], Type::List));
+    module
+        .push_typed_native("join".into(), join)
+        .set_signature(signature([Type::List, STRING], STRING));
+    module
+        .push_contextual_variadic_native("format".into(), Arity::at_least(1), format)
+        .set_signature(signature([STRING], STRING).with_rest(Type::Any));
+    module
+}
+
+/// number of characters of a string.
+fn length(text: String) -> i32 {
+    text.chars().count() as i32
+}
+
+/// string with its letters in upper case.
+fn upper(text: String) -> String {
+    text.to_uppercase()
+}
+
+/// string with its letters in lower case.
+fn lower(text: String) -> String {
+    text.to_lowercase()
+}
+
+/// string without its leading and trailing whitespace.
+fn trim(text: String) -> String {
+    text.trim().to_string()
+}
+
+/// whether a string contains another.
+fn contains(text: String, pattern: String) -> bool {
+    text.contains(&pattern)
+}
+
+/// string with every occurence of a pattern replaced.
+fn replace(text: String, from: String, to: String) -> String {
+    text.replace(&from, &to)
+}
+
+/// characters from a start index up to an end index excluded, by default
+/// the end of the string, `none` when out of range.
+fn slice(context: &mut Context, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut arguments = arguments.into_iter();
+    let mut next = || arguments.next().unwrap_or(Value::None);
+    let text = String::from_value(next())?;
+    let start = i32::from_value(next())?;
+    let length = text.chars().count() as i32;
+    let end = Option::<i32>::from_value(next())?.unwrap_or(length);
+    if start < 0 || end < start || end > length {
+        return Ok(Value::None);
+    }
+    let sliced: String = text
+        .chars()
+        .skip(start as usize)
+        .take((end - start) as usize)
+        .collect();
+    let sliced = sliced.into();
+    context.allocate(&sliced)?;
+    Ok(sliced)
+}
+
+/// parts of a string between the occurences of a separator.
+fn split(text: String, separator: String) -> Vec<String> {
+    text.split(&separator).map(str::to_string).collect()
+}
+
+/// items of a list written one after the other, separated by a string.
+fn join(list: Vec<Value>, separator: String) -> String {
+    let parts: Vec<String> = list.iter().map(Value::to_string).collect();
+    parts.join(&separator)
+}
+
+/// string with each `{}` of a format replaced by the next argument, `none`
+/// when there are fewer arguments than placeholders.
+fn format(context: &mut Context, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut arguments = arguments.into_iter();
+    let format = String::from_value(arguments.next().unwrap_or(Value::None))?;
+    let mut parts = format.split("{}");
+    let mut result = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let Some(argument) = arguments.next() else {
+            return Ok(Value::None);
+        };
+        result.push_str(&argument.to_string());
+        result.push_str(part);
+    }
+    let result = result.into();
+    context.allocate(&result)?;
+    Ok(result)
+}
+
+#[test]
+fn test_length() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval(r#"std::string::length("héllo")"#), 5.into());
+    assert_eq!(eval(r#"std::string::length("")"#), 0.into());
+    assert_eq!(
+        eval_error("std::string::length(1)"),
+        type_mismatch("string", "integer")
+    );
+}
+
+#[test]
+fn test_upper() {
+    use super::{eval, eval_error, string, type_mismatch};
+
+    assert_eq!(eval(r#"std::string::upper("aB")"#), string("AB"));
+    assert_eq!(
+        eval_error("std::string::upper([])"),
+        type_mismatch("string", "list")
+    );
+}
+
+#[test]
+fn test_lower() {
+    use super::{eval, eval_error, string, type_mismatch};
+
+    assert_eq!(eval(r#"std::string::lower("aB")"#), string("ab"));
+    assert_eq!(
+        eval_error("std::string::lower(true)"),
+        type_mismatch("string", "bool")
+    );
+}
+
+#[test]
+fn test_trim() {
+    use super::{eval, eval_error, string, type_mismatch};
+
+    assert_eq!(eval(r#"std::string::trim("  a b ")"#), string("a b"));
+    assert_eq!(
+        eval_error("std::string::trim(1.5)"),
+        type_mismatch("string", "float")
+    );
+}
+
+#[test]
+fn test_contains() {
+    use super::{eval, eval_error, type_mismatch};
+
+    assert_eq!(eval(r#"std::string::contains("abc", "bc")"#), true.into());
+    assert_eq!(eval(r#"std::string::contains("abc", "d")"#), false.into());
+    assert_eq!(
+        eval_error(r#"std::string::contains("abc", 1)"#),
+        type_mismatch("string", "integer")
+    );
+}
+
+#[test]
+fn test_replace() {
+    use super::{eval, eval_error, string, type_mismatch};
+
+    assert_eq!(
+        eval(r#"std::string::replace("a-b-c", "-", "+")"#),
+        string("a+b+c")
+    );
+    assert_eq!(
+        eval_error(r#"std::string::replace("a-b", "-", 1)"#),
+        type_mismatch("string", "integer")
+    );
+}
+
+#[test]
+fn test_slice() {
+    use super::{eval, eval_error, string, type_mismatch};
+
+    assert_eq!(eval(r#"std::string::slice("héllo", 1, 3)"#), string("él"));
+    assert_eq!(eval(r#"std::string::slice("abc", 2, 4)"#), Value::None);
+    assert_eq!(eval(r#"std::string::slice("héllo", 3)"#), string("lo"));
+    assert_eq!(
+        eval_error(r#"std::string::slice("abc", "1")"#),
+        type_mismatch("integer", "string")
+    );
+    assert_eq!(
+        eval_error(r#"std::string::slice("abc", 1, 2.5)"#),
+        type_mismatch("integer", "float")
+    );
+}
+
+#[test]
+fn test_split() {
+    use super::{eval, eval_error, string, type_mismatch};
+
+    assert_eq!(
+        eval(r#"std::string::split("a,b", ",")"#),
+        vec![string("a"), string("b")].into()
+    );
+    assert_eq!(
+        eval_error(r#"std::string::split(1, ",")"#),
+        type_mismatch("string", "integer")
+    );
+}
+
+#[test]
+fn test_join() {
+    use super::{eval, eval_error, string, type_mismatch};
+
+    assert_eq!(
+        eval(r#"std::string::join([1, "a", 2.5], ", ")"#),
+        string("1, a, 2.5")
+    );
+    assert_eq!(
+        eval_error(r#"std::string::join("ab", ", ")"#),
+        type_mismatch("list", "string")
+    );
+}
+
+#[test]
+fn test_format() {
+    use super::{eval, eval_error, string, type_mismatch};
+
+    assert_eq!(
+        eval(r#"std::string::format("{} + {} = {}", 1, 2.5, [3])"#),
+        string("1 + 2.5 = [3]")
+    );
+    assert_eq!(eval(r#"std::string::format("none")"#), string("none"));
+    assert_eq!(eval(r#"std::string::format("{} {}", 1)"#), Value::None);
+    assert_eq!(
+        eval_error("std::string::format(1, 2)"),
+        type_mismatch("string", "integer")
+    );
+}
@@ -0,0 +1,121 @@
+use std::{
+    any::Any,
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+    fmt::Debug,
+    rc::Rc,
+};
+
+use gc::{unsafe_empty_trace, Finalize, Trace};
+use lorgn_lang::ast::Name;
+
+use crate::Function;
+
+type Finalizer = Box<dyn Fn(&mut dyn Any)>;
+
+/// kind of host objects, with the methods scripts can call on them.
+pub struct UserType {
+    name: &'static str,
+    methods: HashMap<Name, Function>,
+    finalizer: Option<Finalizer>,
+}
+
+impl UserType {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            methods: HashMap::new(),
+            finalizer: None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// adds a method, called with the object followed by the arguments.
+    pub fn push_method(&mut self, method: Function) -> &mut Function {
+        let name = method.name().clone();
+        self.methods.entry(name).insert_entry(method).into_mut()
+    }
+
+    pub fn method(&self, name: &Name) -> Option<&Function> {
+        self.methods.get(name)
+    }
+
+    /// sets a function called with the data of objects being collected.
+    pub fn set_finalizer(&mut self, finalizer: impl Fn(&mut dyn Any) + 'static) {
+        self.finalizer = Some(Box::new(finalizer));
+    }
+}
+
+impl Debug for UserType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserType")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// host object held by a value, its data being opaque to scripts.
+pub struct UserData {
+    user_type: Rc<UserType>,
+    data: RefCell<Box<dyn Any>>,
+}
+
+impl UserData {
+    pub fn new(user_type: Rc<UserType>, data: impl Any) -> Self {
+        Self {
+            user_type,
+            data: RefCell::new(Box::new(data)),
+        }
+    }
+
+    pub fn user_type(&self) -> &Rc<UserType> {
+        &self.user_type
+    }
+
+    pub fn is<T: Any>(&self) -> bool {
+        self.data.borrow().is::<T>()
+    }
+
+    /// data as a `T`, `None` when it has another type.
+    pub fn downcast_ref<T: Any>(&self) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.data.borrow(), |data| data.downcast_ref()).ok()
+    }
+
+    pub fn downcast_mut<T: Any>(&self) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(self.data.borrow_mut(), |data| data.downcast_mut()).ok()
+    }
+}
+
+impl Debug for UserData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserData")
+            .field("type", &self.user_type.name)
+            .finish()
+    }
+}
+
+/// objects are only equal to themselves.
+impl PartialEq for UserData {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Finalize for UserData {
+    fn finalize(&self) {
+        let Some(finalizer) = &self.user_type.finalizer else {
+            return;
+        };
+        if let Ok(mut data) = self.data.try_borrow_mut() {
+            finalizer(&mut **data);
+        }
+    }
+}
+
+// host data is not traced, any value it holds stays rooted.
+unsafe impl Trace for UserData {
+    unsafe_empty_trace!();
+}
@@ -1,24 +1,180 @@
-use std::collections::HashMap;
+// `gc_derive` emits its impls inside anonymous consts.
+#![allow(non_local_definitions)]
 
-use gc::Gc;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{Debug, Display},
+    rc::Rc,
+};
+
+use gc::{custom_trace, Finalize, Gc, GcCell, Trace};
 use gc_derive::{Finalize, Trace};
-use lorgn_lang::ast::Name;
+use lorgn_lang::{
+    ast::{Lambda, Name},
+    typing::{Primitive, Type},
+};
+
+use crate::{
+    runtime::{equals, grow_stack, Compiled},
+    UserData,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InnerObj(HashMap<Name, Value>);
+
+impl InnerObj {
+    pub fn get(&self, name: &Name) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    pub fn insert(&mut self, name: Name, value: Value) {
+        self.0.insert(name, value);
+    }
+
+    pub fn remove(&mut self, name: &Name) -> Option<Value> {
+        self.0.remove(name)
+    }
+
+    pub fn contains(&self, name: &Name) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// fields sorted by name.
+    pub fn fields(&self) -> Vec<(&Name, &Value)> {
+        let mut fields: Vec<_> = self.0.iter().collect();
+        fields.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+        fields
+    }
+}
+
+impl Finalize for InnerObj {}
+// names hold no `Gc`, only the values need to be traced.
+unsafe impl Trace for InnerObj {
+    custom_trace!(this, {
+        for value in this.0.values() {
+            mark(value);
+        }
+    });
+}
+
+/// variables captured by a closure when it was created.
+///
+/// captures are copies: the closure does not see later assignments of the
+/// variables, and its own assignments only last for the call making them.
+/// lists and objects are still shared, mutating them is seen on both sides.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Captures(HashMap<Name, Value>);
+
+impl Captures {
+    pub fn insert(&mut self, name: Name, value: Value) {
+        self.0.insert(name, value);
+    }
+
+    pub fn contains(&self, name: &Name) -> bool {
+        self.0.contains_key(name)
+    }
 
-#[derive(Debug, Clone, Trace, Finalize)]
-pub struct InnerObj(#[unsafe_ignore_trace] HashMap<Name, Value>);
+    pub fn get(&self, name: &Name) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Name, &Value)> {
+        self.0.iter()
+    }
+}
+
+impl Finalize for Captures {}
+unsafe impl Trace for Captures {
+    custom_trace!(this, {
+        for value in this.0.values() {
+            mark(value);
+        }
+    });
+}
+
+#[derive(Debug, PartialEq, Trace, Finalize)]
+pub struct Closure {
+    #[unsafe_ignore_trace]
+    pub lambda: Rc<Lambda>,
+    /// module the closure was created in, `None` for the host.
+    #[unsafe_ignore_trace]
+    pub module: Option<Name>,
+    pub captures: Captures,
+    /// body compiled for the bytecode engine, holding no values.
+    #[unsafe_ignore_trace]
+    pub(crate) code: Compiled,
+}
+
+impl Closure {
+    pub fn new(lambda: Rc<Lambda>, module: Option<Name>, captures: Captures) -> Self {
+        Self {
+            lambda,
+            module,
+            captures,
+            code: Compiled::default(),
+        }
+    }
+}
 
-#[derive(Debug, Clone, Trace, Finalize)]
+#[derive(Clone)]
 pub enum Value {
     String(String),
     Integer(i32),
     Float(f32),
     Bool(bool),
-    List(Gc<Vec<Value>>),
-    Object(Gc<InnerObj>),
+    List(Gc<GcCell<Vec<Value>>>),
+    Object(Gc<GcCell<InnerObj>>),
+    /// closure, capturing the visible variables by value.
+    Function(Gc<Closure>),
+    UserData(Gc<UserData>),
     None,
 }
 
+impl Finalize for Value {}
+// lists and objects nested deeply are marked on a grown stack.
+unsafe impl Trace for Value {
+    custom_trace!(this, {
+        grow_stack(|| match this {
+            Value::List(list) => mark(list),
+            Value::Object(object) => mark(object),
+            Value::Function(function) => mark(function),
+            Value::UserData(user_data) => mark(user_data),
+            _ => (),
+        })
+    });
+}
+
 impl Value {
+    pub fn type_of(&self) -> Type {
+        match self {
+            Self::String(_) => Primitive::String.into(),
+            Self::Integer(_) => Primitive::Integer.into(),
+            Self::Float(_) => Primitive::Float.into(),
+            Self::Bool(_) => Primitive::Bool.into(),
+            Self::List(_) => Type::List,
+            Self::Object(_) => Type::Object,
+            Self::Function(_) => Type::Function,
+            Self::UserData(_) => Type::UserData,
+            Self::None => Type::None,
+        }
+    }
+
+    /// name of the value type, host objects giving the name of their own.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::UserData(user_data) => user_data.user_type().name(),
+            other => other.type_of().name(),
+        }
+    }
+
+    pub fn as_user_data(&self) -> Option<&UserData> {
+        match self {
+            Self::UserData(user_data) => Some(user_data),
+            _ => None,
+        }
+    }
+
     pub fn into_string(self) -> Option<String> {
         match &self {
             Self::String(str) => Some(str.clone()),
@@ -37,6 +193,170 @@ impl Value {
             _ => None,
         }
     }
+
+    /// numeric value as a float, integers being promoted.
+    pub fn into_f32(self) -> Option<f32> {
+        match self {
+            Self::Integer(int) => Some(int as f32),
+            Self::Float(flt) => Some(flt),
+            _ => None,
+        }
+    }
+
+    /// address of a list or an object, identifying it among its ancestors.
+    fn address(&self) -> Option<*const ()> {
+        match self {
+            Self::List(list) => Some(&**list as *const _ as *const ()),
+            Self::Object(object) => Some(&**object as *const _ as *const ()),
+            _ => None,
+        }
+    }
+
+    /// writes the value, strings nested in collections being quoted and
+    /// collections already being written shown as `...`.
+    fn write(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        nested: bool,
+        ancestors: &mut Vec<*const ()>,
+    ) -> std::fmt::Result {
+        let address = match self {
+            Self::List(_) | Self::Object(_) => self.address().unwrap(),
+            Self::String(str) if nested => return write!(f, "{str:?}"),
+            Self::String(str) => return f.write_str(str),
+            Self::Integer(int) => return write!(f, "{int}"),
+            Self::Float(flt) => return write!(f, "{flt:?}"),
+            Self::Bool(bool) => return write!(f, "{bool}"),
+            Self::Function(_) => return f.write_str("<function>"),
+            Self::UserData(user_data) => return write!(f, "<{}>", user_data.user_type().name()),
+            Self::None => return f.write_str("none"),
+        };
+        if ancestors.contains(&address) {
+            return f.write_str("...");
+        }
+        ancestors.push(address);
+        match self {
+            Self::List(list) => {
+                f.write_str("[")?;
+                for (index, item) in list.borrow().iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    grow_stack(|| item.write(f, true, ancestors))?;
+                }
+                f.write_str("]")?;
+            }
+            Self::Object(object) => {
+                let object = object.borrow();
+                let fields = object.fields();
+                if fields.is_empty() {
+                    f.write_str("#{}")?;
+                } else {
+                    f.write_str("#{ ")?;
+                    for (index, (Name(name), value)) in fields.into_iter().enumerate() {
+                        if index > 0 {
+                            f.write_str(", ")?;
+                        }
+                        write!(f, "{name}: ")?;
+                        grow_stack(|| value.write(f, true, ancestors))?;
+                    }
+                    f.write_str(" }")?;
+                }
+            }
+            _ => (),
+        }
+        ancestors.pop();
+        Ok(())
+    }
+}
+
+/// values are equal as for the `==` operator, which also compares lists and
+/// objects holding themselves.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        equals(self, other)
+    }
+}
+
+/// value written along with the lists and objects being written, those
+/// met again being shown as `...`.
+struct Nested<'v> {
+    value: &'v Value,
+    ancestors: &'v RefCell<Vec<*const ()>>,
+}
+
+impl Debug for Nested<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        grow_stack(|| self.write(f))
+    }
+}
+
+impl Nested<'_> {
+    fn write(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let nested = |value| Nested {
+            value,
+            ancestors: self.ancestors,
+        };
+        let address = self.value.address();
+        if let Some(address) = address {
+            if self.ancestors.borrow().contains(&address) {
+                return f.write_str("...");
+            }
+            self.ancestors.borrow_mut().push(address);
+        }
+        let result = match self.value {
+            Value::String(str) => f.debug_tuple("String").field(str).finish(),
+            Value::Integer(int) => f.debug_tuple("Integer").field(int).finish(),
+            Value::Float(flt) => f.debug_tuple("Float").field(flt).finish(),
+            Value::Bool(bool) => f.debug_tuple("Bool").field(bool).finish(),
+            Value::List(list) => {
+                let list = list.borrow();
+                f.write_str("List(")?;
+                f.debug_list().entries(list.iter().map(nested)).finish()?;
+                f.write_str(")")
+            }
+            Value::Object(object) => {
+                let object = object.borrow();
+                let fields = object.fields().into_iter();
+                f.write_str("Object(")?;
+                f.debug_map()
+                    .entries(fields.map(|(Name(name), value)| (name, nested(value))))
+                    .finish()?;
+                f.write_str(")")
+            }
+            // the captures are left out, they may hold the closure itself.
+            Value::Function(closure) => {
+                f.write_str("Function(")?;
+                f.debug_struct("Closure")
+                    .field("module", &closure.module)
+                    .finish_non_exhaustive()?;
+                f.write_str(")")
+            }
+            Value::UserData(user_data) => f.debug_tuple("UserData").field(user_data).finish(),
+            Value::None => f.write_str("None"),
+        };
+        if address.is_some() {
+            self.ancestors.borrow_mut().pop();
+        }
+        result
+    }
+}
+
+impl Debug for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ancestors = RefCell::default();
+        Nested {
+            value: self,
+            ancestors: &ancestors,
+        }
+        .fmt(f)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, false, &mut vec![])
+    }
 }
 
 impl From<String> for Value {
@@ -65,18 +385,24 @@ impl From<bool> for Value {
 
 impl From<Vec<Value>> for Value {
     fn from(input: Vec<Value>) -> Self {
-        let gc = Gc::new(input);
+        let gc = Gc::new(GcCell::new(input));
         Self::List(gc)
     }
 }
 
 impl From<HashMap<Name, Value>> for Value {
     fn from(input: HashMap<Name, Value>) -> Self {
-        let gc = Gc::new(InnerObj(input));
+        let gc = Gc::new(GcCell::new(InnerObj(input)));
         Self::Object(gc)
     }
 }
 
+impl From<UserData> for Value {
+    fn from(input: UserData) -> Self {
+        Self::UserData(Gc::new(input))
+    }
+}
+
 impl<T> From<Option<T>> for Value
 where
     T: Into<Value>,